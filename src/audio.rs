@@ -0,0 +1,73 @@
+// Volume, mute and audio output of playbin
+use gst::prelude::*;
+
+use crate::error::{self, Error, Result};
+use crate::record;
+use crate::tutorial3::StreamKind;
+
+// Audio sinks offered to the user, the ones not installed are left out by available_sinks
+pub const AUDIO_SINKS: &[&str] = &[
+    "autoaudiosink",
+    "pulsesink",
+    "pipewiresink",
+    "alsasink",
+    "jackaudiosink",
+    "osxaudiosink",
+    "wasapisink",
+    "directsoundsink",
+    "fakesink",
+];
+
+pub fn available_sinks() -> Vec<&'static str> {
+    AUDIO_SINKS
+        .iter()
+        .copied()
+        .filter(|sink| gst::ElementFactory::find(sink).is_some())
+        .collect()
+}
+
+// Linear volume, 1.0 is 100%
+pub fn volume(playbin: &gst::Element) -> f64 {
+    playbin
+        .get_property("volume")
+        .ok()
+        .and_then(|volume| volume.get_some::<f64>().ok())
+        .unwrap_or(1.0)
+}
+
+// Highest volume of the volume slider. playbin amplifies up to 10.0, which is accepted too.
+pub const MAX_VOLUME: f64 = 1.0;
+
+pub fn set_volume(playbin: &gst::Element, volume: f64) -> Result<()> {
+    if !(0.0..=10.0).contains(&volume) {
+        return Err(Error::Usage(format!("Volume {} is out of 0 - 10", volume)));
+    }
+    error::set_property(playbin, "volume", &volume)
+}
+
+pub fn is_muted(playbin: &gst::Element) -> bool {
+    playbin
+        .get_property("mute")
+        .ok()
+        .and_then(|mute| mute.get_some::<bool>().ok())
+        .unwrap_or(false)
+}
+
+pub fn set_muted(playbin: &gst::Element, muted: bool) -> Result<()> {
+    error::set_property(playbin, "mute", &muted)
+}
+
+// Play the audio into a new sink of the given factory. playbin only accepts a new audio-sink
+// when it is stopped, see player::reconfigure while playing.
+pub fn set_sink(playbin: &gst::Element, factory_name: &str) -> Result<()> {
+    let mut sink = error::make_element(factory_name, None)?;
+    // Keep the tee recordings are attached to in front of the new sink
+    let current = playbin
+        .get_property("audio-sink")
+        .ok()
+        .and_then(|current| current.get::<gst::Element>().ok().flatten());
+    if current.map(|current| record::is_tee_sink(&current)) == Some(true) {
+        sink = record::make_tee_sink(StreamKind::Audio, sink)?;
+    }
+    error::set_property(playbin, "audio-sink", &sink)
+}
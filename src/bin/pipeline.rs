@@ -0,0 +1,25 @@
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::config::{self, PipelineConfig};
+use gstreamer_rust::error::{self, Error, Result};
+use std::path::Path;
+
+// pipeline <description.toml|description.yaml>
+// Build and play a pipeline described in a file, see pipelines/ for examples.
+fn run() -> Result<()> {
+    let args = Args::from_env(&[]);
+    let path = args
+        .positional
+        .first()
+        .ok_or_else(|| Error::Usage("Usage: pipeline <description.toml|.yaml>".to_string()))?;
+    error::init()?;
+    let description = PipelineConfig::load(Path::new(path))?;
+    let pipeline = description.build()?;
+    config::run(&pipeline, &description.bus)
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,10 @@
+use gstreamer_rust::shell;
+
+// shell [description]
+// Interactive gst-launch, type help at the prompt for the commands.
+fn main() {
+    if let Err(err) = shell::run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
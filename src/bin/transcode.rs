@@ -0,0 +1,16 @@
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::error::Result;
+use gstreamer_rust::transcode::{self, Options};
+
+// transcode <media> <output> [--profile webm|matroska|ogg|mp4]
+fn run() -> Result<()> {
+    let options = Options::from_args(&Args::from_env(&[]))?;
+    transcode::run(&options)
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
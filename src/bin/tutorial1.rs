@@ -0,0 +1,9 @@
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::tutorial1;
+
+fn main() {
+    if let Err(err) = tutorial1::run(&MediaSource::from_env()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,8 @@
+use gstreamer_rust::tutorial2;
+
+fn main() {
+    if let Err(err) = tutorial2::run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
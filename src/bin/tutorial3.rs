@@ -0,0 +1,9 @@
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::tutorial3;
+
+fn main() {
+    if let Err(err) = tutorial3::run(&MediaSource::from_env()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
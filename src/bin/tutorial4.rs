@@ -0,0 +1,35 @@
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::error::Result;
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::playlist::Playlist;
+use gstreamer_rust::snapshot;
+use gstreamer_rust::tutorial4::{self, Options};
+
+// tutorial4 [media...] [--loop-start <time>] [--loop-end <time>] [--repeat <count>] [--no-loop]
+//           [--seek-mode key-unit|accurate|snap-before|snap-after] [--rate <rate>]
+//           [--shuffle] [--repeat-mode off|one|all] [--resume|--no-resume]
+//           [--record-profile webm|matroska|ogg|mp4]
+//           [--snapshot-at <time> [--snapshot-output <file>] [--snapshot-size <width>x<height>]]
+// Several media or a M3U/PLS file are played as a playlist.
+// The rate is within 0.25 - 4, negative to play backward.
+// Without --resume or --no-resume, resuming a media played before is asked, unless --loop-start,
+// --loop-end or --repeat is given. A resumed media is played without the default 30s - 35s loop.
+// With --snapshot-at, the frame at that time is saved without playing anything.
+fn run() -> Result<()> {
+    let args = Args::from_env(Options::SWITCHES);
+    if let Some(snapshot) = snapshot::Options::from_args(&args)? {
+        return snapshot::run(&MediaSource::from_cli(&args), &snapshot);
+    }
+    let options = Options::from_args(&args)?;
+    match Playlist::from_cli(&args)? {
+        Some(playlist) => tutorial4::run_playlist(playlist, &options),
+        None => tutorial4::run(&MediaSource::from_cli(&args), &options),
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
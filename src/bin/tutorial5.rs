@@ -0,0 +1,34 @@
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::error::Result;
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::playlist::Playlist;
+use gstreamer_rust::snapshot;
+use gstreamer_rust::tutorial5::{self, Options};
+
+// tutorial5 [media...] [--renderer auto|overlay|gtksink|appsink]
+//           [--subtitles <file>] [--subtitle-font <font>] [--no-subtitles]
+//           [--seek-mode key-unit|accurate|snap-before|snap-after]
+//           [--shuffle] [--repeat-mode off|one|all] [--resume|--no-resume]
+//           [--record-profile webm|matroska|ogg|mp4]
+//           [--snapshot-at <time> [--snapshot-output <file>] [--snapshot-size <width>x<height>]]
+// Several media or a M3U/PLS file are played as a playlist.
+// Without --resume or --no-resume, resuming a media played before is asked.
+// With --snapshot-at, the frame at that time is saved without playing anything.
+fn run() -> Result<()> {
+    let args = Args::from_env(Options::SWITCHES);
+    if let Some(snapshot) = snapshot::Options::from_args(&args)? {
+        return snapshot::run(&MediaSource::from_cli(&args), &snapshot);
+    }
+    let options = Options::from_args(&args)?;
+    match Playlist::from_cli(&args)? {
+        Some(playlist) => tutorial5::run_playlist(playlist, &options),
+        None => tutorial5::run(&MediaSource::from_cli(&args), &options),
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
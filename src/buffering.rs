@@ -0,0 +1,118 @@
+// Buffering of network streams, clock loss and live sources, shared by the players.
+// playbin and uridecodebin buffer network streams in a queue2 and report its level with
+// Buffering messages: the pipeline is paused until the buffer is full, then put back in the state
+// the user asked for. Live sources don't preroll and can't be paused, they are never held.
+use crate::error::{self, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Buffering {
+    // State the user asked for, restored once the buffer is full
+    target: gst::State,
+    // set_state returned NoPreroll, the source produces data whether paused or not
+    live: bool,
+    // Level of the last Buffering message, 100 when not buffering
+    percent: i32,
+}
+
+impl Default for Buffering {
+    fn default() -> Buffering {
+        Buffering {
+            target: gst::State::Null,
+            live: false,
+            percent: 100,
+        }
+    }
+}
+
+impl Buffering {
+    pub fn target(&self) -> gst::State {
+        self.target
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        self.percent < 100
+    }
+
+    pub fn percent(&self) -> i32 {
+        self.percent
+    }
+
+    // The user asked for `state`, returns the state to set on the pipeline.
+    // Playing waits in Paused while buffering.
+    pub fn request(&mut self, state: gst::State) -> gst::State {
+        self.target = state;
+        if state == gst::State::Null || state == gst::State::Ready {
+            // The next media may be neither live nor buffering
+            self.live = false;
+            self.percent = 100;
+        }
+        if state == gst::State::Playing && self.is_buffering() && !self.live {
+            gst::State::Paused
+        } else {
+            state
+        }
+    }
+
+    // Result of the state change done for request
+    pub fn state_changed(&mut self, success: gst::StateChangeSuccess) {
+        if success == gst::StateChangeSuccess::NoPreroll {
+            self.live = true;
+        }
+    }
+
+    // A Buffering message was received, returns the state to set on the pipeline if it must change
+    pub fn update(&mut self, percent: i32) -> Option<gst::State> {
+        let was_buffering = self.is_buffering();
+        self.percent = percent;
+        if self.live || self.target != gst::State::Playing {
+            return None;
+        }
+        match (was_buffering, self.is_buffering()) {
+            (false, true) => Some(gst::State::Paused),
+            (true, false) => Some(gst::State::Playing),
+            _ => None,
+        }
+    }
+
+    // The clock provider went away (e.g. the audio device), returns whether the pipeline must
+    // go through Paused to select a new clock
+    pub fn clock_lost(&self) -> bool {
+        self.target == gst::State::Playing && !self.is_buffering()
+    }
+}
+
+// Set the state the user asked for on `pipeline`, taking buffering into account
+pub fn request_state(
+    pipeline: &gst::Element,
+    buffering: &mut Buffering,
+    state: gst::State,
+) -> Result<()> {
+    let success = error::set_state(pipeline, buffering.request(state))?;
+    buffering.state_changed(success);
+    Ok(())
+}
+
+// Apply a Buffering message
+pub fn handle_percent(
+    pipeline: &gst::Element,
+    buffering: &mut Buffering,
+    percent: i32,
+) -> Result<()> {
+    match buffering.update(percent) {
+        Some(state) => error::set_state(pipeline, state).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+// Apply a ClockLost message, a new clock is selected when going from Paused to Playing
+pub fn handle_clock_lost(pipeline: &gst::Element, buffering: &Buffering) -> Result<()> {
+    if !buffering.clock_lost() {
+        return Ok(());
+    }
+    error::set_state(pipeline, gst::State::Paused)?;
+    error::set_state(pipeline, gst::State::Playing).map(|_| ())
+}
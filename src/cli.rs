@@ -0,0 +1,104 @@
+// Command line parsing shared by the tutorial binaries.
+// Options are given as `--name value` or `--name=value`, switches as `--name`,
+// anything else is a positional argument (the media to play).
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Default, Clone)]
+pub struct Args {
+    pub positional: Vec<String>,
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    // `switches` are the options which don't take a value
+    pub fn parse<I: IntoIterator<Item = String>>(args: I, switches: &[&str]) -> Args {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+            let name = &arg[2..];
+            if let Some(eq) = name.find('=') {
+                parsed
+                    .options
+                    .insert(name[..eq].to_string(), name[eq + 1..].to_string());
+            } else if switches.contains(&name) {
+                parsed.switches.push(name.to_string());
+            } else if let Some(value) = args.next() {
+                parsed.options.insert(name.to_string(), value);
+            }
+        }
+        parsed
+    }
+
+    // Same as parse with the arguments this process was launched with
+    pub fn from_env(switches: &[&str]) -> Args {
+        Args::parse(std::env::args().skip(1), switches)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|v| v.as_str())
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    // Parse the value of an option, None if it was not given
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        match self.get(name) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| Error::Usage(format!("Invalid value {} for --{}", value, name))),
+            None => Ok(None),
+        }
+    }
+
+    // Parse a time option given in seconds (`95.5`) or minutes and seconds (`1:35.5`)
+    pub fn time(&self, name: &str) -> Result<Option<gst::ClockTime>> {
+        match self.get(name) {
+            Some(value) => parse_time(value)
+                .map(Some)
+                .ok_or_else(|| Error::Usage(format!("Invalid time {} for --{}", value, name))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn parse_time(value: &str) -> Option<gst::ClockTime> {
+    let mut seconds = 0.0;
+    for part in value.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    if seconds < 0.0 || !seconds.is_finite() {
+        return None;
+    }
+    Some(gst::ClockTime::from_nseconds(
+        (seconds * 1_000_000_000.0) as u64,
+    ))
+}
+
+// Inverse of parse_time with millisecond precision, e.g. "1:35.500" or "1:02:03.000".
+// "--:--" when the time is unknown.
+pub fn format_time(time: gst::ClockTime) -> String {
+    let mseconds = match time.nseconds() {
+        Some(nseconds) => nseconds / 1_000_000,
+        None => return "--:--".to_string(),
+    };
+    let hours = mseconds / 3_600_000;
+    let minutes = mseconds / 60_000 % 60;
+    let seconds = mseconds / 1000 % 60;
+    let mseconds = mseconds % 1000;
+    if hours > 0 {
+        format!("{}:{:02}:{:02}.{:03}", hours, minutes, seconds, mseconds)
+    } else {
+        format!("{}:{:02}.{:03}", minutes, seconds, mseconds)
+    }
+}
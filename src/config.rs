@@ -0,0 +1,491 @@
+// Pipelines described in TOML or YAML files instead of Rust code: elements with their properties,
+// static and dynamic (pad-added) links, and how the bus messages are handled.
+// The description is validated against the element registry before anything is built.
+//
+//   name = "test-pipeline"
+//   [[elements]]
+//   name = "source"
+//   factory = "videotestsrc"
+//   properties = { pattern = "smpte", num-buffers = 100 }
+//   [[elements]]
+//   name = "sink"
+//   factory = "autovideosink"
+//   [[links]]
+//   src = "source"
+//   sink = "sink"
+use gst::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::error::{self, Error, Result};
+use crate::plugins;
+use crate::streams;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PipelineConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub elements: Vec<ElementConfig>,
+    #[serde(default)]
+    pub links: Vec<LinkConfig>,
+    #[serde(default)]
+    pub bus: BusPolicy,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ElementConfig {
+    pub name: String,
+    pub factory: String,
+    // Converted to the type of the property, see property_value
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropertyValue::Bool(value) => write!(f, "{}", value),
+            PropertyValue::Int(value) => write!(f, "{}", value),
+            PropertyValue::Float(value) => write!(f, "{}", value),
+            PropertyValue::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LinkConfig {
+    pub src: String,
+    pub sink: String,
+    // Pads to link, any compatible pads when not given
+    #[serde(default)]
+    pub src_pad: Option<String>,
+    #[serde(default)]
+    pub sink_pad: Option<String>,
+    // Filter of a static link, or caps a dynamic pad must match to be linked
+    #[serde(default)]
+    pub caps: Option<String>,
+    // The src pads appear later, e.g. uridecodebin or decodebin: link them on pad-added
+    #[serde(default)]
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    // Stop the pipeline and return the error
+    Stop,
+    // Print the error and keep running
+    Ignore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EosPolicy {
+    Stop,
+    // Seek back to the start
+    Loop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct BusPolicy {
+    #[serde(default = "BusPolicy::default_on_error")]
+    pub on_error: ErrorPolicy,
+    #[serde(default = "BusPolicy::default_on_eos")]
+    pub on_eos: EosPolicy,
+    // Stop after this many seconds, None runs until the pipeline stops by itself
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+impl Default for BusPolicy {
+    fn default() -> BusPolicy {
+        BusPolicy {
+            on_error: BusPolicy::default_on_error(),
+            on_eos: BusPolicy::default_on_eos(),
+            timeout_seconds: None,
+        }
+    }
+}
+
+impl BusPolicy {
+    fn default_on_error() -> ErrorPolicy {
+        ErrorPolicy::Stop
+    }
+
+    fn default_on_eos() -> EosPolicy {
+        EosPolicy::Stop
+    }
+}
+
+impl PipelineConfig {
+    pub fn from_toml(content: &str) -> Result<PipelineConfig> {
+        toml::from_str(content).map_err(|err| Error::Config(err.to_string()))
+    }
+
+    pub fn from_yaml(content: &str) -> Result<PipelineConfig> {
+        serde_yaml::from_str(content).map_err(|err| Error::Config(err.to_string()))
+    }
+
+    // Read a .toml, .yaml or .yml file
+    pub fn load(path: &Path) -> Result<PipelineConfig> {
+        let content = std::fs::read_to_string(path).map_err(|error| Error::Io {
+            path: path.display().to_string(),
+            error,
+        })?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => PipelineConfig::from_toml(&content),
+            Some("yaml") | Some("yml") => PipelineConfig::from_yaml(&content),
+            _ => Err(Error::Config(format!(
+                "{} is neither a .toml nor a .yaml file",
+                path.display()
+            ))),
+        }
+    }
+
+    // Factories of the elements, for the preflight check of missing plugins
+    pub fn factories(&self) -> Vec<&str> {
+        self.elements
+            .iter()
+            .map(|element| element.factory.as_str())
+            .collect()
+    }
+
+    // Check the description is consistent, every element is installed and accepts its properties.
+    // GStreamer must be initialized.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for element in &self.elements {
+            if !names.insert(element.name.as_str()) {
+                return Err(Error::Config(format!(
+                    "element {} is declared twice",
+                    element.name
+                )));
+            }
+        }
+        for link in &self.links {
+            for name in &[&link.src, &link.sink] {
+                if !names.contains(name.as_str()) {
+                    return Err(Error::Config(format!(
+                        "link {} -> {} refers to the undeclared element {}",
+                        link.src, link.sink, name
+                    )));
+                }
+            }
+            if let Some(caps) = &link.caps {
+                gst::Caps::from_str(caps)
+                    .map_err(|_| Error::Config(format!("invalid caps {}", caps)))?;
+            }
+        }
+        plugins::check(&self.factories())?;
+
+        // The properties are tried on elements which are thrown away
+        for config in &self.elements {
+            if config.properties.is_empty() {
+                continue;
+            }
+            let element = error::make_element(&config.factory, Some(&config.name))?;
+            for (property, value) in &config.properties {
+                set_property(&element, property, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Validate, then instanciate, configure, add and link the elements
+    pub fn build(&self) -> Result<gst::Pipeline> {
+        self.validate()?;
+        let pipeline = gst::Pipeline::new(self.name.as_deref());
+
+        let mut elements = BTreeMap::new();
+        for config in &self.elements {
+            let element = error::make_element(&config.factory, Some(&config.name))?;
+            for (property, value) in &config.properties {
+                set_property(&element, property, value)?;
+            }
+            error::add_many(pipeline.upcast_ref(), &[&element])?;
+            elements.insert(config.name.as_str(), element);
+        }
+
+        for link in &self.links {
+            let src = &elements[link.src.as_str()];
+            let sink = &elements[link.sink.as_str()];
+            if link.dynamic {
+                connect_dynamic_link(src, sink, link);
+            } else {
+                link_static(src, sink, link)?;
+            }
+        }
+        Ok(pipeline)
+    }
+}
+
+// Convert a value of a description to the type of the property. Numbers may be quoted, enums are
+// given by nick, name or number, flags by nicks joined with +, caps and structures as strings.
+pub fn property_value(
+    element: &gst::Element,
+    property: &str,
+    value: &PropertyValue,
+) -> Result<glib::Value> {
+    let pspec = element
+        .find_property(property)
+        .filter(|pspec| pspec.get_flags().contains(glib::ParamFlags::WRITABLE))
+        .ok_or_else(|| Error::Property {
+            element: element.get_name().to_string(),
+            property: property.to_string(),
+        })?;
+    let value_type = pspec.get_value_type();
+    let text = value.to_string();
+    let converted = match value_type {
+        glib::Type::Bool => match value {
+            PropertyValue::Bool(value) => Some(value.to_value()),
+            PropertyValue::String(value) => {
+                value.parse::<bool>().ok().map(|value| value.to_value())
+            }
+            _ => None,
+        },
+        glib::Type::I32 => integer::<i32>(value),
+        glib::Type::U32 => integer::<u32>(value),
+        glib::Type::I64 => integer::<i64>(value),
+        glib::Type::U64 => integer::<u64>(value),
+        glib::Type::F32 => number(value).map(|value| (value as f32).to_value()),
+        glib::Type::F64 => number(value).map(|value| value.to_value()),
+        glib::Type::String => Some(text.as_str().to_value()),
+        _ => {
+            if let Some(class) = glib::EnumClass::new(value_type) {
+                match value {
+                    PropertyValue::Int(value) => i32::try_from(*value)
+                        .ok()
+                        .and_then(|value| class.to_value(value)),
+                    _ => class
+                        .to_value_by_nick(&text)
+                        .or_else(|| class.to_value_by_name(&text)),
+                }
+            } else if let Some(class) = glib::FlagsClass::new(value_type) {
+                match value {
+                    PropertyValue::Int(value) => u32::try_from(*value)
+                        .ok()
+                        .and_then(|value| class.builder().set(value).build()),
+                    _ => text
+                        .split('+')
+                        .fold(class.builder(), |builder, nick| {
+                            builder.set_by_nick(nick.trim())
+                        })
+                        .build(),
+                }
+            } else if value_type == gst::Caps::static_type() {
+                gst::Caps::from_str(&text).ok().map(|caps| caps.to_value())
+            } else if value_type == gst::Structure::static_type() {
+                gst::Structure::from_str(&text)
+                    .ok()
+                    .map(|structure| structure.to_value())
+            } else {
+                return Err(Error::Config(format!(
+                    "the property {} of {} has the type {}, which can't be described",
+                    property,
+                    element.get_name(),
+                    value_type.name()
+                )));
+            }
+        }
+    };
+    converted.ok_or_else(|| {
+        Error::Config(format!(
+            "invalid value {} for the property {} of {} ({})",
+            text,
+            property,
+            element.get_name(),
+            value_type.name()
+        ))
+    })
+}
+
+fn integer<T>(value: &PropertyValue) -> Option<glib::Value>
+where
+    T: TryFrom<i64> + FromStr + glib::ToValue,
+{
+    match value {
+        PropertyValue::Int(value) => T::try_from(*value).ok().map(|value| value.to_value()),
+        PropertyValue::String(value) => value.parse::<T>().ok().map(|value| value.to_value()),
+        _ => None,
+    }
+}
+
+fn number(value: &PropertyValue) -> Option<f64> {
+    match value {
+        PropertyValue::Int(value) => Some(*value as f64),
+        PropertyValue::Float(value) => Some(*value),
+        PropertyValue::String(value) => value.parse().ok(),
+        PropertyValue::Bool(_) => None,
+    }
+}
+
+// Convert and set a property. GObject ignores numbers out of the range of the property with a
+// warning, so they are found by reading the property back.
+pub fn set_property(element: &gst::Element, property: &str, value: &PropertyValue) -> Result<()> {
+    let converted = property_value(element, property, value)?;
+    error::set_property(element, property, &converted)?;
+
+    let numeric = matches!(
+        converted.type_(),
+        glib::Type::I32
+            | glib::Type::U32
+            | glib::Type::I64
+            | glib::Type::U64
+            | glib::Type::F32
+            | glib::Type::F64
+    );
+    let readable = element
+        .find_property(property)
+        .map(|pspec| pspec.get_flags().contains(glib::ParamFlags::READABLE))
+        .unwrap_or(false);
+    if !numeric || !readable {
+        return Ok(());
+    }
+    let applied = element
+        .get_property(property)
+        .map_err(|_| Error::Property {
+            element: element.get_name().to_string(),
+            property: property.to_string(),
+        })?;
+    if streams::value_to_string(&applied) != streams::value_to_string(&converted) {
+        return Err(Error::Config(format!(
+            "{} is out of the range of the property {} of {}",
+            value,
+            property,
+            element.get_name()
+        )));
+    }
+    Ok(())
+}
+
+fn link_static(src: &gst::Element, sink: &gst::Element, link: &LinkConfig) -> Result<()> {
+    let link_error = || Error::Link {
+        src: link.src.clone(),
+        sink: link.sink.clone(),
+    };
+    let caps = link.caps.as_deref().and_then(|caps| caps.parse().ok());
+    match (&link.src_pad, &link.sink_pad) {
+        (None, None) => src
+            .link_filtered(sink, caps.as_ref())
+            .map_err(|_| link_error()),
+        (src_pad, sink_pad) => src
+            .link_pads_filtered(src_pad.as_deref(), sink, sink_pad.as_deref(), caps.as_ref())
+            .map_err(|_| link_error()),
+    }
+}
+
+// Link the pads of `src` when they are added, like tutorial3 does by hand.
+// Pads which don't match the caps of the link are left for the other links.
+fn connect_dynamic_link(src: &gst::Element, sink: &gst::Element, link: &LinkConfig) {
+    let sink_weak = sink.downgrade();
+    let link = link.clone();
+    src.connect_pad_added(move |_, src_pad| {
+        let sink = match sink_weak.upgrade() {
+            Some(sink) => sink,
+            None => return,
+        };
+        if let Some(caps) = link
+            .caps
+            .as_deref()
+            .and_then(|caps| caps.parse::<gst::Caps>().ok())
+        {
+            let matches = src_pad
+                .get_current_caps()
+                .or_else(|| src_pad.query_caps(None))
+                .map(|pad_caps| pad_caps.can_intersect(&caps))
+                .unwrap_or(false);
+            if !matches {
+                return;
+            }
+        }
+        if let Some(src_pad_name) = &link.src_pad {
+            if src_pad.get_name().as_str() != src_pad_name {
+                return;
+            }
+        }
+        let sink_pad = match &link.sink_pad {
+            Some(name) => sink.get_static_pad(name),
+            None => sink.get_compatible_pad(src_pad, None),
+        };
+        let res = match sink_pad {
+            // Another pad matching the link was linked already
+            Some(sink_pad) if sink_pad.is_linked() => return,
+            Some(sink_pad) => error::link_pads(src_pad, &sink_pad),
+            None => Err(Error::Link {
+                src: src_pad.get_path_string().to_string(),
+                sink: link.sink.clone(),
+            }),
+        };
+        if let Err(err) = res {
+            eprintln!("{}", err);
+        }
+    });
+}
+
+// Play the pipeline, handling the bus messages as described by `policy`
+pub fn run(pipeline: &gst::Pipeline, policy: &BusPolicy) -> Result<()> {
+    error::set_state(pipeline, gst::State::Playing)?;
+
+    let bus = error::bus(pipeline)?;
+    let deadline = policy
+        .timeout_seconds
+        .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+    let mut res = Ok(());
+    loop {
+        let timeout = match deadline {
+            Some(deadline) if Instant::now() >= deadline => break,
+            Some(deadline) => gst::ClockTime::from_mseconds(
+                deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_millis() as u64,
+            ),
+            None => gst::CLOCK_TIME_NONE,
+        };
+        let msg = match bus.timed_pop(timeout) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        match msg.view() {
+            gst::MessageView::Error(err) => {
+                let err = Error::from_message(err);
+                match policy.on_error {
+                    ErrorPolicy::Stop => {
+                        res = Err(err);
+                        break;
+                    }
+                    ErrorPolicy::Ignore => eprintln!("{}", err),
+                }
+            }
+            gst::MessageView::Eos(..) => match policy.on_eos {
+                EosPolicy::Stop => break,
+                EosPolicy::Loop => {
+                    if let Err(err) = pipeline.seek_simple(
+                        gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+                        gst::ClockTime::from_seconds(0),
+                    ) {
+                        eprintln!("Could not loop: {}", err);
+                        break;
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    error::set_state(pipeline, gst::State::Null)?;
+    res
+}
@@ -0,0 +1,203 @@
+// Errors of every pipeline step, so that callers can recover or report them instead of panicking
+use gst::prelude::*;
+use std::fmt;
+
+use crate::plugins::MissingPlugin;
+
+#[derive(Debug)]
+pub enum Error {
+    // GStreamer itself could not be initialized
+    Init(glib::Error),
+    // GTK could not be initialized, e.g. there is no display
+    Gtk(String),
+    // Invalid command line arguments
+    Usage(String),
+    // An element could not be instanciated, the plugin providing it is most likely not installed
+    MissingElement(String),
+    // Plugins found missing by the preflight check or reported by playbin/uridecodebin while running
+    MissingPlugins(Vec<MissingPlugin>),
+    // Two elements or pads could not be linked, or an element could not be added to a bin
+    Link {
+        src: String,
+        sink: String,
+    },
+    // A property doesn't exist or the value has a wrong type
+    Property {
+        element: String,
+        property: String,
+    },
+    // The pipeline refused to change its state
+    StateChange {
+        element: String,
+        state: gst::State,
+    },
+    // An element has no bus, i.e. it isn't a pipeline nor in one
+    NoBus(String),
+    // An element has no static pad of this name
+    MissingPad {
+        element: String,
+        pad: String,
+    },
+    // State shared with callbacks was still in use when the playback ended
+    StillShared(&'static str),
+    // Position, duration, seeking or any other query was not answered
+    Query(&'static str),
+    // A seek or step event was not handled
+    Seek(String),
+    // A settings or state file could not be read or written
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    // A pipeline description could not be parsed or is inconsistent
+    Config(String),
+    // A gst-launch style description was rejected by parse_launch
+    Launch(glib::Error),
+    // A recording could not be started or its file finalized
+    Record(String),
+    // The frame being shown could not be saved as an image
+    Snapshot(String),
+    // An error message was posted on the bus while running
+    Bus {
+        src: Option<String>,
+        error: glib::Error,
+        debug: Option<String>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // Convert an error message received from the bus
+    pub fn from_message(err: gst::message::Error) -> Error {
+        Error::Bus {
+            src: err.get_src().map(|s| s.get_path_string().to_string()),
+            error: err.get_error(),
+            debug: err.get_debug().map(|d| d.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Init(err) => write!(f, "Failed to initialize GStreamer: {}", err),
+            Error::Gtk(reason) => write!(f, "Failed to initialize GTK: {}", reason),
+            Error::Usage(msg) => write!(f, "{}", msg),
+            Error::MissingElement(factory) => write!(
+                f,
+                "Could not instanciate {}, check the plugin providing it is installed",
+                MissingPlugin::for_element(factory)
+            ),
+            Error::MissingPlugins(missing) => {
+                writeln!(f, "Your GStreamer installation is missing some plugins:")?;
+                for plugin in missing {
+                    writeln!(f, "    {}", plugin)?;
+                }
+                Ok(())
+            }
+            Error::Link { src, sink } => write!(f, "Could not link {} to {}", src, sink),
+            Error::Property { element, property } => {
+                write!(f, "Could not set the property {} on {}", property, element)
+            }
+            Error::StateChange { element, state } => {
+                write!(f, "Unable to set {} to the {:?} state", element, state)
+            }
+            Error::NoBus(element) => write!(f, "{} has no bus", element),
+            Error::MissingPad { element, pad } => write!(f, "{} has no pad {}", element, pad),
+            Error::StillShared(what) => write!(f, "The {} is still in use", what),
+            Error::Query(query) => write!(f, "Could not query {}", query),
+            Error::Seek(reason) => write!(f, "Failed to seek: {}", reason),
+            Error::Io { path, error } => write!(f, "Could not access {}: {}", path, error),
+            Error::Config(reason) => write!(f, "Invalid pipeline configuration: {}", reason),
+            Error::Launch(err) => write!(f, "Could not parse the pipeline description: {}", err),
+            Error::Record(reason) => write!(f, "Recording failed: {}", reason),
+            Error::Snapshot(reason) => write!(f, "Could not take a snapshot: {}", reason),
+            Error::Bus { src, error, debug } => write!(
+                f,
+                "Error received from element {:?}: {} ({:?})",
+                src, error, debug
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn init() -> Result<()> {
+    gst::init().map_err(Error::Init)
+}
+
+// gst::ElementFactory::make reporting which element is missing
+pub fn make_element(factory_name: &str, name: Option<&str>) -> Result<gst::Element> {
+    gst::ElementFactory::make(factory_name, name)
+        .map_err(|_| Error::MissingElement(factory_name.to_string()))
+}
+
+pub fn set_property<E: IsA<gst::Element>, V: glib::ToValue>(
+    element: &E,
+    property: &str,
+    value: &V,
+) -> Result<()> {
+    element
+        .set_property(property, value)
+        .map_err(|_| Error::Property {
+            element: element.get_name().to_string(),
+            property: property.to_string(),
+        })
+}
+
+pub fn set_state<E: IsA<gst::Element>>(
+    element: &E,
+    state: gst::State,
+) -> Result<gst::StateChangeSuccess> {
+    element.set_state(state).map_err(|_| Error::StateChange {
+        element: element.get_name().to_string(),
+        state,
+    })
+}
+
+pub fn bus<E: IsA<gst::Element>>(element: &E) -> Result<gst::Bus> {
+    element
+        .get_bus()
+        .ok_or_else(|| Error::NoBus(element.get_name().to_string()))
+}
+
+pub fn static_pad<E: IsA<gst::Element>>(element: &E, name: &str) -> Result<gst::Pad> {
+    element
+        .get_static_pad(name)
+        .ok_or_else(|| Error::MissingPad {
+            element: element.get_name().to_string(),
+            pad: name.to_string(),
+        })
+}
+
+// Link the elements one after another, reporting the first pair which failed
+pub fn link_many(elements: &[&gst::Element]) -> Result<()> {
+    for pair in elements.windows(2) {
+        pair[0].link(pair[1]).map_err(|_| Error::Link {
+            src: pair[0].get_name().to_string(),
+            sink: pair[1].get_name().to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+pub fn link_pads(src_pad: &gst::Pad, sink_pad: &gst::Pad) -> Result<()> {
+    src_pad.link(sink_pad).map(|_| ()).map_err(|_| Error::Link {
+        src: src_pad.get_path_string().to_string(),
+        sink: sink_pad.get_path_string().to_string(),
+    })
+}
+
+// Add elements to a bin. This only fails when an element already has a parent.
+pub fn add_many(bin: &gst::Bin, elements: &[&gst::Element]) -> Result<()> {
+    bin.add_many(elements).map_err(|_| Error::Link {
+        src: elements
+            .iter()
+            .map(|e| e.get_name().to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        sink: bin.get_name().to_string(),
+    })
+}
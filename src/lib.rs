@@ -0,0 +1,33 @@
+// Pipeline building blocks of each tutorial, usable from other crates.
+// Every tutorial binary in src/bin is a thin wrapper around the `run` function of its module.
+extern crate gstreamer as gst;
+extern crate gstreamer_app as gst_app;
+extern crate gstreamer_pbutils as gst_pbutils;
+extern crate gstreamer_video as gst_video;
+
+pub mod audio;
+pub mod buffering;
+pub mod cli;
+pub mod config;
+pub mod error;
+pub mod media;
+pub mod player;
+pub mod playlist;
+pub mod plugins;
+pub mod record;
+pub mod resume;
+pub mod seek;
+pub mod settings;
+pub mod shell;
+pub mod snapshot;
+pub mod streams;
+pub mod subtitles;
+pub mod transcode;
+pub mod tutorial1;
+pub mod tutorial2;
+pub mod tutorial3;
+pub mod tutorial4;
+
+// gtk and gdk are only available if --features tutorial5 was specified on build
+#[cfg(feature = "tutorial5")]
+pub mod tutorial5;
@@ -0,0 +1,160 @@
+// Media source selection shared by every tutorial binary.
+//
+// The media to play can be given as the first command line argument, with `--uri <media>`,
+// or with the GST_TUTORIAL_MEDIA environment variable. It accepts
+//   * an URI (https://..., file:///..., rtsp://...)
+//   * a local file path, which is converted to a file:// URI
+//   * `videotestsrc` or `audiotestsrc` to play a synthetic source without any file
+
+use gst::prelude::*;
+use std::path::Path;
+
+use crate::cli::Args;
+use crate::error::{self, Result};
+
+// The trailer every tutorial used to play when nothing is specified
+pub const DEFAULT_URI: &str =
+    "https://www.freedesktop.org/software/gstreamer-sdk/data/media/sintel_trailer-480p.webm";
+
+// Environment variable read when no media was given on the command line
+pub const MEDIA_ENV: &str = "GST_TUTORIAL_MEDIA";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaSource {
+    Uri(String),
+    VideoTest,
+    AudioTest,
+}
+
+impl MediaSource {
+    // Interpret a string given by the user as media source
+    pub fn parse(media: &str) -> MediaSource {
+        match media {
+            "videotestsrc" => MediaSource::VideoTest,
+            "audiotestsrc" => MediaSource::AudioTest,
+            _ if gst::uri_is_valid(media) => MediaSource::Uri(media.to_string()),
+            _ => MediaSource::Uri(path_to_uri(media)),
+        }
+    }
+
+    // Pick the media from `--uri` or the first positional argument, then from the environment,
+    // then fallback to the default trailer. Other options are left to the binaries.
+    pub fn from_cli(args: &Args) -> MediaSource {
+        let media = args
+            .get("uri")
+            .map(|uri| uri.to_string())
+            .or_else(|| args.positional.first().cloned())
+            .or_else(|| std::env::var(MEDIA_ENV).ok());
+        match media {
+            Some(media) => MediaSource::parse(&media),
+            None => MediaSource::Uri(DEFAULT_URI.to_string()),
+        }
+    }
+
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> MediaSource {
+        MediaSource::from_cli(&Args::parse(args, &[]))
+    }
+
+    // Same as from_args with the arguments this process was launched with
+    pub fn from_env() -> MediaSource {
+        MediaSource::from_args(std::env::args().skip(1))
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        match self {
+            MediaSource::Uri(uri) => Some(uri),
+            _ => None,
+        }
+    }
+
+    // Elements instanciated by make_source, for the preflight check of missing plugins
+    pub fn source_elements(&self) -> &'static [&'static str] {
+        match self {
+            MediaSource::Uri(_) => &["uridecodebin"],
+            MediaSource::VideoTest => &["videotestsrc"],
+            MediaSource::AudioTest => &["audiotestsrc"],
+        }
+    }
+
+    // Elements instanciated by make_player
+    pub fn player_elements(&self) -> &'static [&'static str] {
+        match self {
+            MediaSource::Uri(_) => &["playbin"],
+            MediaSource::VideoTest => &["videotestsrc", "videoconvert", "autovideosink"],
+            MediaSource::AudioTest => &["audiotestsrc", "audioconvert", "autoaudiosink"],
+        }
+    }
+
+    // Instanciate the element producing decoded streams.
+    // URIs are decoded by uridecodebin which exposes its pads later on `pad-added`,
+    // synthetic sources have a static src pad. Use connect_src_pads to handle both.
+    pub fn make_source(&self, name: Option<&str>) -> Result<gst::Element> {
+        match self {
+            MediaSource::Uri(uri) => {
+                let source = error::make_element("uridecodebin", name)?;
+                error::set_property(&source, "uri", &uri.as_str())?;
+                Ok(source)
+            }
+            MediaSource::VideoTest => error::make_element("videotestsrc", name),
+            MediaSource::AudioTest => error::make_element("audiotestsrc", name),
+        }
+    }
+
+    // Instanciate a complete player for this media.
+    // URIs are played with playbin. Synthetic sources can not be opened by playbin,
+    // so they are played by a small pipeline (testsrc ! convert ! autosink) instead.
+    pub fn make_player(&self, name: Option<&str>) -> Result<gst::Element> {
+        let (source, convert, sink) = match self {
+            MediaSource::Uri(uri) => {
+                let playbin = error::make_element("playbin", name)?;
+                error::set_property(&playbin, "uri", &uri.as_str())?;
+                return Ok(playbin);
+            }
+            MediaSource::VideoTest => ("videotestsrc", "videoconvert", "autovideosink"),
+            MediaSource::AudioTest => ("audiotestsrc", "audioconvert", "autoaudiosink"),
+        };
+        let source = error::make_element(source, Some("source"))?;
+        let convert = error::make_element(convert, Some("convert"))?;
+        let sink = error::make_element(sink, Some("sink"))?;
+
+        let pipeline = gst::Pipeline::new(name);
+        error::add_many(pipeline.upcast_ref(), &[&source, &convert, &sink])?;
+        error::link_many(&[&source, &convert, &sink])?;
+        Ok(pipeline.upcast())
+    }
+}
+
+// Call `func` for every src pad of an element made by MediaSource::make_source.
+// Static pads are handled immediately, dynamic pads when they are added.
+pub fn connect_src_pads<F>(source: &gst::Element, func: F)
+where
+    F: Fn(&gst::Pad) + Send + Sync + 'static,
+{
+    match source.get_static_pad("src") {
+        Some(src_pad) => func(&src_pad),
+        None => {
+            source.connect_pad_added(move |_, src_pad| func(src_pad));
+        }
+    }
+}
+
+// URI of a location given by the user, which is either an URI or a local file path
+pub fn to_uri(location: &str) -> String {
+    if gst::uri_is_valid(location) {
+        location.to_string()
+    } else {
+        path_to_uri(location)
+    }
+}
+
+// Convert a (possibly relative) local path into a file:// URI
+fn path_to_uri(path: &str) -> String {
+    let path = Path::new(path);
+    let absolute = path
+        .canonicalize()
+        .unwrap_or_else(|_| std::env::current_dir().unwrap_or_default().join(path));
+    match glib::filename_to_uri(&absolute, None) {
+        Ok(uri) => uri.to_string(),
+        Err(_) => format!("file://{}", absolute.display()),
+    }
+}
@@ -0,0 +1,291 @@
+// Event-driven player core shared by the command line and GTK players.
+// The bus is watched from the GLib main loop instead of being polled, and everything the
+// front-ends are interested in is reported to them as a PlayerEvent.
+use gst::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use crate::audio;
+use crate::buffering::{self, Buffering};
+use crate::error::{self, Error, Result};
+use crate::plugins::{self, MissingPlugin};
+
+// How often the position is reported
+pub const POSITION_INTERVAL_MS: u32 = 100;
+
+#[derive(Debug)]
+pub enum PlayerEvent {
+    // State of the pipeline itself, changes of its children are not reported
+    StateChanged {
+        old: gst::State,
+        current: gst::State,
+    },
+    // Reported every POSITION_INTERVAL_MS once the position is known.
+    // duration is CLOCK_TIME_NONE while it can't be queried.
+    Position {
+        position: gst::ClockTime,
+        duration: gst::ClockTime,
+    },
+    // A new stream started, e.g. playbin switched to the next entry of a playlist
+    StreamStart,
+    // The duration changed, the next Position carries the new one
+    DurationChanged,
+    // Percentage of the buffer filled by a network source. The player already paused the pipeline
+    // below 100% and resumed it at 100%, unless the source is live.
+    Buffering(i32),
+    // The clock went away, the player already selected a new one by going through Paused
+    ClockLost,
+    // playbin's volume or mute changed, by the application or by the audio sink
+    Volume {
+        volume: f64,
+        muted: bool,
+    },
+    // A segment seek reached its stop position
+    SegmentDone,
+    // playbin can't play a stream, an Error follows
+    MissingPlugin(MissingPlugin),
+    // Application message posted on the bus, by the name of its structure
+    Application(String),
+    // The playback failed. Missing plugins reported before are summarized instead of the generic error.
+    Error(Error),
+    Eos,
+}
+
+// Watches a pipeline for as long as it lives. Events are handled on the thread default main context,
+// so the front-end must run a main loop on it: gtk::main() or a glib::MainLoop.
+pub struct Player {
+    pipeline: gst::Element,
+    context: glib::MainContext,
+    sources: Vec<glib::SourceId>,
+    notify_handlers: Vec<glib::SignalHandlerId>,
+    buffering: Rc<RefCell<Buffering>>,
+}
+
+impl Player {
+    pub fn new<F>(pipeline: gst::Element, handler: F) -> Result<Player>
+    where
+        F: FnMut(PlayerEvent) + 'static,
+    {
+        Player::with_buffering(
+            pipeline,
+            Rc::new(RefCell::new(Buffering::default())),
+            handler,
+        )
+    }
+
+    // Same as new, with the buffering state shared with controls changing the state themselves
+    // through buffering::request_state
+    pub fn with_buffering<F>(
+        pipeline: gst::Element,
+        buffering: Rc<RefCell<Buffering>>,
+        mut handler: F,
+    ) -> Result<Player>
+    where
+        F: FnMut(PlayerEvent) + 'static,
+    {
+        let context = glib::MainContext::ref_thread_default();
+        // The bus watch and the timer must be Send, the handler doesn't have to:
+        // they send the events through a channel received on the main context.
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+        let bus = error::bus(&pipeline)?;
+        let pipeline_weak = pipeline.downgrade();
+        let bus_sender = sender.clone();
+        let mut missing_plugins = Vec::new();
+        let bus_watch = bus.create_watch(None, glib::PRIORITY_DEFAULT, move |_, msg| {
+            let pipeline = match pipeline_weak.upgrade() {
+                Some(pipeline) => pipeline,
+                None => return glib::Continue(false),
+            };
+            match event_from_message(&pipeline, msg, &mut missing_plugins) {
+                Some(event) => glib::Continue(bus_sender.send(event).is_ok()),
+                None => glib::Continue(true),
+            }
+        });
+
+        // Notifications come from any thread, e.g. the audio sink changing the system volume
+        let mut notify_handlers = Vec::new();
+        if pipeline.find_property("volume").is_some() {
+            for property in &["volume", "mute"] {
+                let notify_sender = Mutex::new(sender.clone());
+                let handler_id = pipeline.connect_notify(Some(property), move |pipeline, _| {
+                    let _ = notify_sender.lock().unwrap().send(PlayerEvent::Volume {
+                        volume: audio::volume(pipeline),
+                        muted: audio::is_muted(pipeline),
+                    });
+                });
+                notify_handlers.push(handler_id);
+            }
+        }
+
+        let pipeline_weak = pipeline.downgrade();
+        let timer = glib::timeout_source_new(
+            POSITION_INTERVAL_MS,
+            None,
+            glib::PRIORITY_DEFAULT,
+            move || {
+                let pipeline = match pipeline_weak.upgrade() {
+                    Some(pipeline) => pipeline,
+                    None => return glib::Continue(false),
+                };
+                // The stream may have no position yet, try again on next timeout
+                let position = match pipeline.query_position::<gst::ClockTime>() {
+                    Some(position) => position,
+                    None => return glib::Continue(true),
+                };
+                let duration = pipeline
+                    .query_duration::<gst::ClockTime>()
+                    .unwrap_or(gst::CLOCK_TIME_NONE);
+                glib::Continue(
+                    sender
+                        .send(PlayerEvent::Position { position, duration })
+                        .is_ok(),
+                )
+            },
+        );
+
+        // Buffering and clock loss are handled before the front-end is told about them
+        let pipeline_weak = pipeline.downgrade();
+        let receiver_buffering = buffering.clone();
+        let sources = vec![
+            bus_watch.attach(Some(&context)),
+            timer.attach(Some(&context)),
+            receiver.attach(Some(&context), move |event| {
+                if let Some(pipeline) = pipeline_weak.upgrade() {
+                    let mut buffering = receiver_buffering.borrow_mut();
+                    let res = match &event {
+                        PlayerEvent::Buffering(percent) => {
+                            buffering::handle_percent(&pipeline, &mut buffering, *percent)
+                        }
+                        PlayerEvent::ClockLost => {
+                            buffering::handle_clock_lost(&pipeline, &buffering)
+                        }
+                        _ => Ok(()),
+                    };
+                    drop(buffering);
+                    if let Err(err) = res {
+                        handler(PlayerEvent::Error(err));
+                    }
+                }
+                handler(event);
+                glib::Continue(true)
+            }),
+        ];
+        Ok(Player {
+            pipeline,
+            context,
+            sources,
+            notify_handlers,
+            buffering,
+        })
+    }
+
+    pub fn pipeline(&self) -> &gst::Element {
+        &self.pipeline
+    }
+
+    // Stays paused until the buffer of a network stream is full
+    pub fn play(&self) -> Result<()> {
+        self.request_state(gst::State::Playing)
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        self.request_state(gst::State::Paused)
+    }
+
+    // Release the resources of the pipeline, events stop
+    pub fn stop(&self) -> Result<()> {
+        self.request_state(gst::State::Null)
+    }
+
+    // The source is live, e.g. a camera or a live network stream, it's never paused for buffering
+    pub fn is_live(&self) -> bool {
+        self.buffering.borrow().is_live()
+    }
+
+    fn request_state(&self, state: gst::State) -> Result<()> {
+        buffering::request_state(&self.pipeline, &mut self.buffering.borrow_mut(), state)
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        for handler_id in self.notify_handlers.drain(..) {
+            self.pipeline.disconnect(handler_id);
+        }
+        // The sources belong to self.context, which is not necessarily the default one
+        for source_id in &self.sources {
+            if let Some(source) = self.context.find_source_by_id(source_id) {
+                source.destroy();
+            }
+        }
+    }
+}
+
+// Apply settings which playbin only accepts when stopped (e.g. suburi, audio-sink) while playing.
+// The pipeline is brought back to READY, configured and restored to its state.
+// Returns the position to seek to once it is prerolled again.
+pub fn reconfigure<F>(pipeline: &gst::Element, configure: F) -> Result<Option<gst::ClockTime>>
+where
+    F: FnOnce() -> Result<()>,
+{
+    let position = pipeline.query_position::<gst::ClockTime>();
+    let (_, current, pending) = pipeline.get_state(gst::ClockTime::from_seconds(0));
+    let state = if pending == gst::State::VoidPending {
+        current
+    } else {
+        pending
+    };
+
+    error::set_state(pipeline, gst::State::Ready)?;
+    configure()?;
+    if state == gst::State::Paused || state == gst::State::Playing {
+        error::set_state(pipeline, state)?;
+    }
+    Ok(position)
+}
+
+// Translate a bus message, None for the ones no front-end is interested in
+fn event_from_message(
+    pipeline: &gst::Element,
+    msg: &gst::Message,
+    missing_plugins: &mut Vec<MissingPlugin>,
+) -> Option<PlayerEvent> {
+    match msg.view() {
+        gst::MessageView::Error(err) => Some(PlayerEvent::Error(plugins::error_with_missing(
+            Error::from_message(err),
+            std::mem::take(missing_plugins),
+        ))),
+        gst::MessageView::Element(..) => MissingPlugin::from_message(msg).map(|plugin| {
+            missing_plugins.push(plugin.clone());
+            PlayerEvent::MissingPlugin(plugin)
+        }),
+        gst::MessageView::Eos(..) => Some(PlayerEvent::Eos),
+        gst::MessageView::SegmentDone(..) => Some(PlayerEvent::SegmentDone),
+        gst::MessageView::StreamStart(..) => Some(PlayerEvent::StreamStart),
+        gst::MessageView::DurationChanged(..) => Some(PlayerEvent::DurationChanged),
+        gst::MessageView::Buffering(buffering) => {
+            Some(PlayerEvent::Buffering(buffering.get_percent()))
+        }
+        gst::MessageView::ClockLost(..) => Some(PlayerEvent::ClockLost),
+        gst::MessageView::Application(application) => application
+            .get_structure()
+            .map(|s| PlayerEvent::Application(s.get_name().to_string())),
+        gst::MessageView::StateChanged(state_changed) => {
+            if state_changed
+                .get_src()
+                .map(|s| s == *pipeline)
+                .unwrap_or(false)
+            {
+                Some(PlayerEvent::StateChanged {
+                    old: state_changed.get_old(),
+                    current: state_changed.get_current(),
+                })
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
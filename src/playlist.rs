@@ -0,0 +1,298 @@
+// Playlist of several media: URIs given on the command line or read from M3U/PLS files,
+// with shuffle and repeat modes. Played gaplessly by queueing the next URI on playbin's
+// about-to-finish signal.
+use gst::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::cli::Args;
+use crate::error::{self, Error, Result};
+use crate::media;
+use crate::player;
+
+// Playlist files which are read instead of being played
+pub const EXTENSIONS: &[&str] = &["m3u", "m3u8", "pls"];
+
+// Name of the application messages asking the player to skip, posted on the bus by the front-ends
+pub const NEXT_MESSAGE: &str = "playlist-next";
+pub const PREVIOUS_MESSAGE: &str = "playlist-previous";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepeatMode {
+    // Stop after the last entry
+    Off,
+    // Play the current entry again and again, skipping still moves to the next one
+    One,
+    // Start over after the last entry
+    All,
+}
+
+impl Default for RepeatMode {
+    fn default() -> RepeatMode {
+        RepeatMode::Off
+    }
+}
+
+impl RepeatMode {
+    pub const ALL: [RepeatMode; 3] = [RepeatMode::Off, RepeatMode::One, RepeatMode::All];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::One => "one",
+            RepeatMode::All => "all",
+        }
+    }
+}
+
+impl FromStr for RepeatMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<RepeatMode> {
+        RepeatMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| Error::Usage(format!("Unknown repeat mode {}", s)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playlist {
+    uris: Vec<String>,
+    // Indices of uris in the order they are played, shuffled or not
+    order: Vec<usize>,
+    // Index in order of the current entry
+    position: usize,
+    shuffle: bool,
+    repeat: RepeatMode,
+}
+
+impl Playlist {
+    pub fn new(uris: Vec<String>) -> Result<Playlist> {
+        if uris.is_empty() {
+            return Err(Error::Usage("The playlist is empty".to_string()));
+        }
+        Ok(Playlist {
+            order: (0..uris.len()).collect(),
+            uris,
+            position: 0,
+            shuffle: false,
+            repeat: RepeatMode::default(),
+        })
+    }
+
+    // Media given as paths or URIs, playlist files are replaced by their entries
+    pub fn from_locations(locations: &[String]) -> Result<Playlist> {
+        let mut uris = Vec::new();
+        for location in locations {
+            if is_playlist_file(location) {
+                uris.extend(load(location)?);
+            } else {
+                uris.push(media::to_uri(location));
+            }
+        }
+        Playlist::new(uris)
+    }
+
+    // Playlist of the positional arguments, with --shuffle and --repeat-mode off|one|all.
+    // None when there is a single media which isn't a playlist file, it is played on its own.
+    pub fn from_cli(args: &Args) -> Result<Option<Playlist>> {
+        let is_playlist = match args.positional.as_slice() {
+            [] => false,
+            [location] => is_playlist_file(location),
+            _ => true,
+        };
+        if !is_playlist {
+            return Ok(None);
+        }
+        let mut playlist = Playlist::from_locations(&args.positional)?;
+        playlist.set_repeat_mode(args.parse_value("repeat-mode")?.unwrap_or_default());
+        playlist.set_shuffle(args.has("shuffle"));
+        Ok(Some(playlist))
+    }
+
+    pub fn uris(&self) -> &[String] {
+        &self.uris
+    }
+
+    pub fn len(&self) -> usize {
+        self.uris.len()
+    }
+
+    // Never true, a playlist has at least one entry
+    pub fn is_empty(&self) -> bool {
+        self.uris.is_empty()
+    }
+
+    pub fn current(&self) -> &str {
+        &self.uris[self.current_index()]
+    }
+
+    // Index in uris of the current entry
+    pub fn current_index(&self) -> usize {
+        self.order[self.position]
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffle
+    }
+
+    // The current entry stays current, the other ones are played in random order after it
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.current_index();
+        self.shuffle = shuffle;
+        if shuffle {
+            let mut rest: Vec<usize> = (0..self.uris.len()).filter(|i| *i != current).collect();
+            // Fisher-Yates, with GLib's random numbers
+            for i in (1..rest.len()).rev() {
+                let j = glib::random_int_range(0, i as i32 + 1) as usize;
+                rest.swap(i, j);
+            }
+            self.order = std::iter::once(current).chain(rest).collect();
+            self.position = 0;
+        } else {
+            self.order = (0..self.uris.len()).collect();
+            self.position = current;
+        }
+    }
+
+    pub fn repeat_mode(&self) -> RepeatMode {
+        self.repeat
+    }
+
+    pub fn set_repeat_mode(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    // Skip to the next entry, None at the end when not repeating
+    pub fn next(&mut self) -> Option<&str> {
+        if self.position + 1 < self.order.len() {
+            self.position += 1;
+        } else if self.repeat != RepeatMode::Off {
+            self.position = 0;
+        } else {
+            return None;
+        }
+        Some(self.current())
+    }
+
+    // Skip to the previous entry, None at the start when not repeating
+    pub fn previous(&mut self) -> Option<&str> {
+        if self.position > 0 {
+            self.position -= 1;
+        } else if self.repeat != RepeatMode::Off {
+            self.position = self.order.len() - 1;
+        } else {
+            return None;
+        }
+        Some(self.current())
+    }
+
+    // Entry to play once the current one finished, None when the playlist is over
+    pub fn advance(&mut self) -> Option<&str> {
+        if self.repeat == RepeatMode::One {
+            Some(self.current())
+        } else {
+            self.next()
+        }
+    }
+}
+
+pub fn is_playlist_file(location: &str) -> bool {
+    Path::new(location)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Read the URIs of a local M3U or PLS file, given as a path or a file:// URI
+pub fn load(location: &str) -> Result<Vec<String>> {
+    let path = if gst::uri_is_valid(location) {
+        glib::filename_from_uri(location)
+            .map(|(path, _)| path)
+            .map_err(|_| Error::Usage(format!("Only local playlists can be read: {}", location)))?
+    } else {
+        PathBuf::from(location)
+    };
+    let content = std::fs::read_to_string(&path).map_err(|error| Error::Io {
+        path: path.display().to_string(),
+        error,
+    })?;
+    // Relative entries are relative to the playlist
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let is_pls = path
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("pls"))
+        .unwrap_or(false);
+    Ok(if is_pls {
+        parse_pls(&content, base)
+    } else {
+        parse_m3u(&content, base)
+    })
+}
+
+// One entry per line, lines starting with # are comments or #EXTINF information
+pub fn parse_m3u(content: &str, base: &Path) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| entry_uri(line, base))
+        .collect()
+}
+
+// FileN=<entry> lines of the [playlist] section, ordered by N
+pub fn parse_pls(content: &str, base: &Path) -> Vec<String> {
+    let mut entries: Vec<(u32, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let eq = line.find('=')?;
+            let (key, value) = (line[..eq].trim(), line[eq + 1..].trim());
+            if !key.get(..4)?.eq_ignore_ascii_case("file") {
+                return None;
+            }
+            Some((key.get(4..)?.parse().ok()?, value))
+        })
+        .collect();
+    entries.sort_by_key(|(number, _)| *number);
+    entries
+        .into_iter()
+        .map(|(_, entry)| entry_uri(entry, base))
+        .collect()
+}
+
+fn entry_uri(entry: &str, base: &Path) -> String {
+    if gst::uri_is_valid(entry) {
+        entry.to_string()
+    } else {
+        media::to_uri(&base.join(entry).to_string_lossy())
+    }
+}
+
+// Queue the next entry when playbin is about to finish the current one, so that it is played
+// without gap. playbin posts EOS instead when the playlist is over.
+pub fn connect_gapless(playbin: &gst::Element, playlist: Arc<Mutex<Playlist>>) -> Result<()> {
+    playbin
+        .connect("about-to-finish", false, move |values| {
+            let playbin = values[0].get::<gst::Element>().ok().flatten()?;
+            if let Some(uri) = playlist.lock().unwrap().advance() {
+                if let Err(err) = error::set_property(&playbin, "uri", &uri) {
+                    eprintln!("{}", err);
+                }
+            }
+            None
+        })
+        .map(|_| ())
+        .map_err(|_| Error::Property {
+            element: playbin.get_name().to_string(),
+            property: "about-to-finish".to_string(),
+        })
+}
+
+// Switch playbin to another entry right away, keeping it paused or playing
+pub fn play_entry(playbin: &gst::Element, uri: &str) -> Result<()> {
+    player::reconfigure(playbin, || error::set_property(playbin, "uri", &uri)).map(|_| ())
+}
@@ -0,0 +1,133 @@
+// Detection of missing plugins, before building a pipeline and while it runs
+use std::fmt;
+
+use crate::error::{Error, Result};
+
+// Element, plugin providing it and the package (GStreamer module) the plugin is distributed in
+const KNOWN_ELEMENTS: &[(&str, &str, &str)] = &[
+    ("fakesink", "coreelements", "gstreamer"),
+    ("filesink", "coreelements", "gstreamer"),
+    ("filesrc", "coreelements", "gstreamer"),
+    ("queue", "coreelements", "gstreamer"),
+    ("tee", "coreelements", "gstreamer"),
+    ("appsink", "app", "gst-plugins-base"),
+    ("appsrc", "app", "gst-plugins-base"),
+    ("audioconvert", "audioconvert", "gst-plugins-base"),
+    ("audioresample", "audioresample", "gst-plugins-base"),
+    ("audiotestsrc", "audiotestsrc", "gst-plugins-base"),
+    ("decodebin", "playback", "gst-plugins-base"),
+    ("encodebin", "encoding", "gst-plugins-base"),
+    ("glsinkbin", "opengl", "gst-plugins-base"),
+    ("oggmux", "ogg", "gst-plugins-base"),
+    ("opusenc", "opus", "gst-plugins-base"),
+    ("playbin", "playback", "gst-plugins-base"),
+    ("theoraenc", "theora", "gst-plugins-base"),
+    ("uridecodebin", "playback", "gst-plugins-base"),
+    ("videoconvert", "videoconvert", "gst-plugins-base"),
+    ("videoscale", "videoscale", "gst-plugins-base"),
+    ("videotestsrc", "videotestsrc", "gst-plugins-base"),
+    ("vorbisenc", "vorbis", "gst-plugins-base"),
+    ("ximagesink", "ximagesink", "gst-plugins-base"),
+    ("autoaudiosink", "autodetect", "gst-plugins-good"),
+    ("autovideosink", "autodetect", "gst-plugins-good"),
+    ("gtkglsink", "gtk", "gst-plugins-good"),
+    ("gtksink", "gtk", "gst-plugins-good"),
+    ("jpegenc", "jpeg", "gst-plugins-good"),
+    ("matroskamux", "matroska", "gst-plugins-good"),
+    ("mp4mux", "isomp4", "gst-plugins-good"),
+    ("pngenc", "png", "gst-plugins-good"),
+    ("scaletempo", "audiofx", "gst-plugins-good"),
+    ("souphttpsrc", "soup", "gst-plugins-good"),
+    ("vp8enc", "vpx", "gst-plugins-good"),
+    ("webmmux", "matroska", "gst-plugins-good"),
+    ("x264enc", "x264", "gst-plugins-ugly"),
+    ("avenc_aac", "libav", "gst-libav"),
+];
+
+// Something GStreamer needs but which isn't installed
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingPlugin {
+    // Element factory name, or the description posted by GStreamer for runtime messages
+    pub name: String,
+    // Plugin and package providing it, if known
+    pub plugin: Option<&'static str>,
+    pub package: Option<&'static str>,
+}
+
+impl MissingPlugin {
+    pub fn for_element(element: &str) -> MissingPlugin {
+        let known = KNOWN_ELEMENTS.iter().find(|(name, _, _)| *name == element);
+        MissingPlugin {
+            name: element.to_string(),
+            plugin: known.map(|(_, plugin, _)| *plugin),
+            package: known.map(|(_, _, package)| *package),
+        }
+    }
+
+    // Parse the `missing-plugin` element message posted by playbin or uridecodebin
+    pub fn from_message(msg: &gst::Message) -> Option<MissingPlugin> {
+        let structure = match msg.view() {
+            gst::MessageView::Element(element) => element.get_structure()?,
+            _ => return None,
+        };
+        if structure.get_name() != "missing-plugin" {
+            return None;
+        }
+        // `name` is a human readable description, `detail` the caps or element which was needed
+        let name = structure
+            .get::<String>("name")
+            .ok()
+            .flatten()
+            .or_else(|| structure.get::<String>("detail").ok().flatten())
+            .unwrap_or_else(|| "unknown plugin".to_string());
+        Some(MissingPlugin {
+            name,
+            plugin: None,
+            package: None,
+        })
+    }
+}
+
+impl fmt::Display for MissingPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.plugin, self.package) {
+            (Some(plugin), Some(package)) => {
+                write!(f, "{} (plugin {}, install {})", self.name, plugin, package)
+            }
+            _ => write!(f, "{}", self.name),
+        }
+    }
+}
+
+// Look every element up in the registry and return the ones which are not installed
+pub fn find_missing(elements: &[&str]) -> Vec<MissingPlugin> {
+    let mut missing: Vec<MissingPlugin> = Vec::new();
+    for element in elements {
+        if gst::ElementFactory::find(element).is_none()
+            && !missing.iter().any(|m| m.name == *element)
+        {
+            missing.push(MissingPlugin::for_element(element));
+        }
+    }
+    missing
+}
+
+// Preflight check run before building a pipeline. GStreamer must be initialized.
+pub fn check(elements: &[&str]) -> Result<()> {
+    let missing = find_missing(elements);
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MissingPlugins(missing))
+    }
+}
+
+// playbin and uridecodebin post `missing-plugin` messages and then a generic error.
+// Report the missing plugins instead of that error when there are some.
+pub fn error_with_missing(err: Error, missing: Vec<MissingPlugin>) -> Error {
+    if missing.is_empty() {
+        err
+    } else {
+        Error::MissingPlugins(missing)
+    }
+}
@@ -0,0 +1,452 @@
+// Recording what is played, without interrupting the playback.
+// A tee is inserted in front of playbin's video and audio sinks before playing. A recording attaches
+// an encodebin ! filesink branch to the tees, and is detached with idle probes: the branch is unlinked
+// between two buffers and gets EOS, so that the muxer writes its index and headers before the file
+// is closed. Stopping doesn't block: the recording is finished once FINALIZED_MESSAGE is posted.
+use gst::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::{self, Error, Result};
+use crate::plugins;
+use crate::transcode::Profile;
+use crate::tutorial3::StreamKind;
+
+// Posted as application message to start or stop recording, e.g. from the commands typed on stdin
+pub const RECORD_MESSAGE: &str = "record-toggle";
+
+// Posted as application message once the EOS of a recording reached its file
+pub const FINALIZED_MESSAGE: &str = "record-finalized";
+
+// How long a stopped recording is given to finalize its file
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+const TEE_NAME: &str = "record-tee";
+
+// Sink property of playbin, name of the wrapping bin and request pad of encodebin for each stream
+fn stream_names(kind: StreamKind) -> (&'static str, &'static str, &'static str) {
+    match kind {
+        StreamKind::Video => ("video-sink", "video-record-sink", "video_%u"),
+        _ => ("audio-sink", "audio-record-sink", "audio_%u"),
+    }
+}
+
+// Bin playing into `sink` through a tee the recordings are attached to: tee ! queue ! sink
+pub fn make_tee_sink(kind: StreamKind, sink: gst::Element) -> Result<gst::Element> {
+    let (_, bin_name, _) = stream_names(kind);
+    let bin = gst::Bin::new(Some(bin_name));
+    let tee = error::make_element("tee", Some(TEE_NAME))?;
+    // The recording pads are left unlinked between recordings, which must not stop the playback
+    error::set_property(&tee, "allow-not-linked", &true)?;
+    let queue = error::make_element("queue", None)?;
+    error::add_many(&bin, &[&tee, &queue, &sink])?;
+    error::link_many(&[&tee, &queue, &sink])?;
+
+    let ghost_error = || Error::Link {
+        src: bin_name.to_string(),
+        sink: TEE_NAME.to_string(),
+    };
+    let tee_sink_pad = error::static_pad(&tee, "sink")?;
+    let ghost = gst::GhostPad::new(Some("sink"), &tee_sink_pad).map_err(|_| ghost_error())?;
+    bin.add_pad(&ghost).map_err(|_| ghost_error())?;
+    Ok(bin.upcast())
+}
+
+pub fn is_tee_sink(element: &gst::Element) -> bool {
+    element
+        .downcast_ref::<gst::Bin>()
+        .and_then(|bin| bin.get_by_name(TEE_NAME))
+        .is_some()
+}
+
+fn sink_of(playbin: &gst::Element, property: &str) -> Option<gst::Element> {
+    playbin
+        .get_property(property)
+        .ok()?
+        .get::<gst::Element>()
+        .ok()
+        .flatten()
+}
+
+// Put a tee in front of the video and audio sinks of playbin, keeping the sinks which were set.
+// Must be called before playing. Returns false if `playbin` isn't a playbin, e.g. for synthetic sources.
+pub fn use_tee_sinks(playbin: &gst::Element) -> Result<bool> {
+    if playbin.find_property("video-sink").is_none() {
+        return Ok(false);
+    }
+    for &(kind, default_sink) in &[
+        (StreamKind::Video, "autovideosink"),
+        (StreamKind::Audio, "autoaudiosink"),
+    ] {
+        let (property, _, _) = stream_names(kind);
+        let sink = match sink_of(playbin, property) {
+            Some(sink) if is_tee_sink(&sink) => continue,
+            Some(sink) => sink,
+            None => error::make_element(default_sink, None)?,
+        };
+        error::set_property(playbin, property, &make_tee_sink(kind, sink)?)?;
+    }
+    Ok(true)
+}
+
+// Tees of the streams being played. A media without audio doesn't use the audio sink for instance.
+fn playing_tees(playbin: &gst::Element) -> Vec<(StreamKind, gst::Element)> {
+    [StreamKind::Video, StreamKind::Audio]
+        .iter()
+        .filter_map(|&kind| {
+            let sink = sink_of(playbin, stream_names(kind).0)?;
+            let linked = sink
+                .get_static_pad("sink")
+                .map(|pad| pad.is_linked())
+                .unwrap_or(false);
+            if !linked {
+                return None;
+            }
+            let tee = sink.downcast_ref::<gst::Bin>()?.get_by_name(TEE_NAME)?;
+            Some((kind, tee))
+        })
+        .collect()
+}
+
+// recording-<seconds since the epoch>.<extension> in the videos directory, or the current one
+pub fn default_location(profile: Profile) -> PathBuf {
+    let dir = glib::get_user_special_dir(glib::UserDirectory::Videos)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    dir.join(format!("recording-{}.{}", now, profile.extension()))
+}
+
+// Running time of the pipeline, the time the recorded streams start at
+fn running_time(pipeline: &gst::Element) -> gst::ClockTime {
+    if pipeline.get_current_state() != gst::State::Playing {
+        // Paused, the running time is frozen where the pipeline was paused
+        return pipeline.get_start_time();
+    }
+    match pipeline.get_clock() {
+        Some(clock) => clock.get_time() - pipeline.get_base_time(),
+        None => gst::ClockTime::from_nseconds(0),
+    }
+}
+
+// Make `pad` linkable from the elements of `ancestor`, with ghost pads on every bin in between.
+// Returns the outermost pad and the ghost pads with their bin, innermost first.
+fn expose(
+    pad: &gst::Pad,
+    ancestor: &gst::Bin,
+) -> Result<(gst::Pad, Vec<(gst::Bin, gst::GhostPad)>)> {
+    let link_error = || Error::Link {
+        src: pad.get_path_string().to_string(),
+        sink: ancestor.get_name().to_string(),
+    };
+    let mut outer = pad.clone();
+    let mut ghosts = Vec::new();
+    let mut parent = pad
+        .get_parent_element()
+        .and_then(|element| element.get_parent());
+    while let Some(bin) = parent
+        .take()
+        .and_then(|parent| parent.downcast::<gst::Bin>().ok())
+    {
+        if &bin == ancestor {
+            return Ok((outer, ghosts));
+        }
+        let ghost = gst::GhostPad::new(None, &outer).map_err(|_| link_error())?;
+        ghost.set_active(true).map_err(|_| link_error())?;
+        bin.add_pad(&ghost).map_err(|_| link_error())?;
+        outer = ghost.clone().upcast();
+        parent = bin.get_parent();
+        ghosts.push((bin, ghost));
+    }
+    Err(link_error())
+}
+
+// Tee pad of a stream linked to the recorder
+struct Branch {
+    tee: gst::Element,
+    tee_pad: gst::Pad,
+    // Pad linked to the recorder and the ghost pads leading to it
+    outer: gst::Pad,
+    ghosts: Vec<(gst::Bin, gst::GhostPad)>,
+    // Pad of the recorder bin, leading to a request pad of encodebin
+    sink_pad: gst::Pad,
+}
+
+// A recording in progress. It must be stopped, or the file is left unfinished.
+pub struct Recording {
+    location: PathBuf,
+    pipeline: gst::Bin,
+    // encodebin ! filesink
+    recorder: gst::Bin,
+    branches: Vec<Branch>,
+    // Set once the EOS reached the filesink, i.e. the muxer is done with the file
+    finished: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Recording {
+    // Record the streams played by `playbin` into `location`, encoded with `profile`.
+    // use_tee_sinks must have been called before playing.
+    pub fn start(playbin: &gst::Element, location: &Path, profile: Profile) -> Result<Recording> {
+        let mut elements = vec!["encodebin", "filesink"];
+        elements.extend(profile.required_elements());
+        plugins::check(&elements)?;
+
+        let pipeline = playbin
+            .downcast_ref::<gst::Bin>()
+            .cloned()
+            .ok_or_else(|| Error::Record(format!("{} is not a bin", playbin.get_name())))?;
+        let tees = playing_tees(playbin);
+        if tees.is_empty() {
+            return Err(Error::Record(
+                "nothing to record, the sinks have no tee or are not playing".to_string(),
+            ));
+        }
+
+        let encodebin = error::make_element("encodebin", None)?;
+        error::set_property(&encodebin, "profile", &profile.encoding_profile()?)?;
+        let sink = error::make_element("filesink", None)?;
+        error::set_property(&sink, "location", &location.display().to_string())?;
+        // Named by GStreamer, the previous recorder may still be finalizing in the pipeline
+        let recorder = gst::Bin::new(None);
+        error::add_many(&recorder, &[&encodebin, &sink])?;
+        error::link_many(&[&encodebin, &sink])?;
+
+        let finished = Arc::new((Mutex::new(false), Condvar::new()));
+        let sink_finished = finished.clone();
+        error::static_pad(&sink, "sink")?.add_probe(
+            gst::PadProbeType::EVENT_DOWNSTREAM,
+            move |pad, info| {
+                if let Some(gst::PadProbeData::Event(event)) = &info.data {
+                    if event.get_type() == gst::EventType::Eos {
+                        let (lock, condvar) = &*sink_finished;
+                        *lock.lock().unwrap() = true;
+                        condvar.notify_all();
+                        // Tell the application thread, the recording can be finished there
+                        if let Some(sink) = pad.get_parent_element() {
+                            let _ = sink.post_message(
+                                &gst::Message::new_application(gst::Structure::new_empty(
+                                    FINALIZED_MESSAGE,
+                                ))
+                                .build(),
+                            );
+                        }
+                    }
+                }
+                gst::PadProbeReturn::Ok
+            },
+        );
+
+        error::add_many(&pipeline, &[recorder.upcast_ref()])?;
+        recorder
+            .sync_state_with_parent()
+            .map_err(|_| Error::StateChange {
+                element: recorder.get_name().to_string(),
+                state: pipeline.get_current_state(),
+            })?;
+
+        // The recorded streams start at 0 rather than where the playback is
+        let offset = running_time(playbin).nseconds().unwrap_or(0) as i64;
+        let mut recording = Recording {
+            location: location.to_path_buf(),
+            pipeline,
+            recorder,
+            branches: Vec::new(),
+            finished,
+        };
+        for (kind, tee) in tees {
+            let branch = recording.attach(kind, tee, &encodebin, offset);
+            match branch {
+                Ok(branch) => recording.branches.push(branch),
+                Err(err) => {
+                    // Clean up what was attached already, the file is useless anyway
+                    recording.abort();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(recording)
+    }
+
+    // Link a new pad of the tee to encodebin. It is blocked meanwhile, so the first buffer the
+    // recorder gets has the caps and segment of the stream.
+    fn attach(
+        &self,
+        kind: StreamKind,
+        tee: gst::Element,
+        encodebin: &gst::Element,
+        offset: i64,
+    ) -> Result<Branch> {
+        let (_, _, template) = stream_names(kind);
+        let link_error = || Error::Link {
+            src: tee.get_path_string().to_string(),
+            sink: encodebin.get_name().to_string(),
+        };
+        let tee_pad = tee.get_request_pad("src_%u").ok_or_else(link_error)?;
+        let block = tee_pad.add_probe(gst::PadProbeType::BLOCK_DOWNSTREAM, |_, _| {
+            gst::PadProbeReturn::Ok
+        });
+
+        let res = expose(&tee_pad, &self.pipeline).and_then(|(outer, ghosts)| {
+            // encodebin is inside the recorder bin, which needs a pad too
+            let encoder_pad = encodebin.get_request_pad(template).ok_or_else(link_error)?;
+            let sink_pad = gst::GhostPad::new(None, &encoder_pad).map_err(|_| link_error())?;
+            sink_pad.set_active(true).map_err(|_| link_error())?;
+            self.recorder.add_pad(&sink_pad).map_err(|_| link_error())?;
+            let sink_pad = sink_pad.upcast();
+            error::link_pads(&outer, &sink_pad)?;
+            Ok((outer, ghosts, sink_pad))
+        });
+        tee_pad.set_offset(-offset);
+        if let Some(block) = block {
+            tee_pad.remove_probe(block);
+        }
+
+        match res {
+            Ok((outer, ghosts, sink_pad)) => Ok(Branch {
+                tee,
+                tee_pad,
+                outer,
+                ghosts,
+                sink_pad,
+            }),
+            Err(err) => {
+                tee.release_request_pad(&tee_pad);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn location(&self) -> &Path {
+        &self.location
+    }
+
+    // Detach the recorder, the playback goes on. Returns right away, the muxer finalizes the file
+    // meanwhile.
+    pub fn stop(self) -> StoppingRecording {
+        for branch in &self.branches {
+            let outer = branch.outer.clone();
+            let sink_pad = branch.sink_pad.clone();
+            // Called between two buffers, or right away when no data flows (paused or EOS)
+            branch
+                .tee_pad
+                .add_probe(gst::PadProbeType::IDLE, move |_, _| {
+                    let _ = outer.unlink(&sink_pad);
+                    sink_pad.send_event(gst::Event::new_eos().build());
+                    gst::PadProbeReturn::Remove
+                });
+        }
+        StoppingRecording {
+            recording: self,
+            deadline: Instant::now() + STOP_TIMEOUT,
+        }
+    }
+
+    // Detach the recorder without finalizing the file, when it could not be started
+    fn abort(self) {
+        for branch in &self.branches {
+            let _ = branch.outer.unlink(&branch.sink_pad);
+        }
+        if let Err(err) = self.remove() {
+            eprintln!("{}", err);
+        }
+    }
+
+    fn is_finalized(&self) -> bool {
+        *self.finished.0.lock().unwrap()
+    }
+
+    // Close the file and remove the recorder from the pipeline
+    fn remove(&self) -> Result<()> {
+        error::set_state(&self.recorder, gst::State::Null)?;
+        self.pipeline
+            .remove(&self.recorder)
+            .map_err(|_| Error::Record(format!("could not remove {}", self.recorder.get_name())))?;
+        for branch in &self.branches {
+            // The outermost ghost pads point to the inner ones, they go first
+            for (bin, ghost) in branch.ghosts.iter().rev() {
+                let _ = bin.remove_pad(ghost);
+            }
+            branch.tee.release_request_pad(&branch.tee_pad);
+        }
+        Ok(())
+    }
+}
+
+// A recording detached from the tees, whose file is being finalized. It must be finished once done,
+// on FINALIZED_MESSAGE or after STOP_TIMEOUT, see Finalizing.
+pub struct StoppingRecording {
+    recording: Recording,
+    deadline: Instant,
+}
+
+impl StoppingRecording {
+    pub fn location(&self) -> &Path {
+        &self.recording.location
+    }
+
+    // Once every stream got EOS the muxer writes its index and headers, then the EOS reaches the
+    // filesink. It is there already when the playback reached the end.
+    pub fn is_done(&self) -> bool {
+        self.recording.is_finalized() || Instant::now() >= self.deadline
+    }
+
+    // Block until done, for when the playback is stopping anyway
+    pub fn wait(&self) {
+        let (lock, condvar) = &*self.recording.finished;
+        let timeout = self.deadline.saturating_duration_since(Instant::now());
+        let _ = condvar
+            .wait_timeout_while(lock.lock().unwrap(), timeout, |finished| !*finished)
+            .unwrap();
+    }
+
+    // Remove the recorder, returning the file unless the muxer didn't finalize it in time
+    pub fn finish(self) -> Result<PathBuf> {
+        let complete = self.recording.is_finalized();
+        self.recording.remove()?;
+        if complete {
+            Ok(self.recording.location)
+        } else {
+            Err(Error::Record(format!(
+                "{} was not finalized within {}s",
+                self.recording.location.display(),
+                STOP_TIMEOUT.as_secs()
+            )))
+        }
+    }
+}
+
+// Recordings which were stopped, finished from the main loop of the player
+#[derive(Default)]
+pub struct Finalizing(Vec<StoppingRecording>);
+
+impl Finalizing {
+    pub fn push(&mut self, recording: StoppingRecording) {
+        self.0.push(recording);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // Finish the recordings which are done, on FINALIZED_MESSAGE and periodically for the timeout
+    pub fn finish_done(&mut self) -> Vec<Result<PathBuf>> {
+        let (done, pending): (Vec<_>, Vec<_>) =
+            self.0.drain(..).partition(StoppingRecording::is_done);
+        self.0 = pending;
+        done.into_iter().map(StoppingRecording::finish).collect()
+    }
+
+    // Wait for every recording and finish them, when the playback stops
+    pub fn finish_all(&mut self) -> Vec<Result<PathBuf>> {
+        self.0
+            .drain(..)
+            .map(|recording| {
+                recording.wait();
+                recording.finish()
+            })
+            .collect()
+    }
+}
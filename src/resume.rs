@@ -0,0 +1,214 @@
+// Playback state remembered per URI, so that the players resume where they left off.
+// Stored as JSON in $XDG_DATA_HOME/gstreamer-rust/resume.json
+use gst::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audio;
+use crate::cli::Args;
+use crate::error::{Error, Result};
+use crate::streams;
+use crate::subtitles;
+use crate::tutorial3::StreamKind;
+
+// Media stopped closer than this to the start or to the end start over next time
+pub const MARGIN_SECONDS: u64 = 5;
+
+// Only the most recently played media are remembered
+pub const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumeState {
+    // Position and duration in nanoseconds
+    pub position: u64,
+    pub duration: Option<u64>,
+    pub video_track: Option<i32>,
+    pub audio_track: Option<i32>,
+    // None when the subtitles were turned off
+    pub text_track: Option<i32>,
+    pub volume: Option<f64>,
+    pub rate: f64,
+    // Seconds since the epoch, to forget the oldest entries
+    pub updated: u64,
+}
+
+impl ResumeState {
+    // State of playbin playing at `rate`, None until the position is known
+    pub fn capture(playbin: &gst::Element, rate: f64) -> Option<ResumeState> {
+        let position = playbin.query_position::<gst::ClockTime>()?.nseconds()?;
+        let duration = playbin
+            .query_duration::<gst::ClockTime>()
+            .and_then(|duration| duration.nseconds());
+        let text_track = if subtitles::is_enabled(playbin) {
+            streams::current(playbin, StreamKind::Text)
+        } else {
+            None
+        };
+        let volume = if playbin.find_property("volume").is_some() {
+            Some(audio::volume(playbin))
+        } else {
+            None
+        };
+        Some(ResumeState {
+            position,
+            duration,
+            video_track: streams::current(playbin, StreamKind::Video),
+            audio_track: streams::current(playbin, StreamKind::Audio),
+            text_track,
+            volume,
+            rate,
+            updated: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+        })
+    }
+
+    pub fn position(&self) -> gst::ClockTime {
+        gst::ClockTime::from_nseconds(self.position)
+    }
+
+    // Neither at the very start nor at the end
+    pub fn is_resumable(&self) -> bool {
+        let margin = MARGIN_SECONDS * 1_000_000_000;
+        self.position > margin
+            && self
+                .duration
+                .map(|duration| self.position + margin < duration)
+                .unwrap_or(true)
+    }
+
+    // Select the tracks and set the volume again. The streams must be known, i.e. prerolled.
+    // The position and rate are restored by seeking.
+    pub fn restore_tracks(&self, playbin: &gst::Element) -> Result<()> {
+        if let Some(index) = self.video_track {
+            streams::select(playbin, StreamKind::Video, index)?;
+        }
+        if let Some(index) = self.audio_track {
+            streams::select(playbin, StreamKind::Audio, index)?;
+        }
+        if playbin.find_property("suburi").is_some() {
+            subtitles::select_track(playbin, self.text_track)?;
+        }
+        if let Some(volume) = self.volume {
+            audio::set_volume(playbin, volume)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResumeDb {
+    entries: BTreeMap<String, ResumeState>,
+}
+
+impl ResumeDb {
+    // $XDG_DATA_HOME/gstreamer-rust/resume.json
+    pub fn default_path() -> PathBuf {
+        glib::get_user_data_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("gstreamer-rust")
+            .join("resume.json")
+    }
+
+    // A missing or corrupted database is empty, everything starts from the beginning then
+    pub fn load() -> ResumeDb {
+        ResumeDb::load_from(&ResumeDb::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> ResumeDb {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        ResumeDb { entries }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&ResumeDb::default_path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let io_error = |error| Error::Io {
+            path: path.display().to_string(),
+            error,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let content =
+            serde_json::to_string_pretty(&self.entries).map_err(|error| io_error(error.into()))?;
+        fs::write(path, content).map_err(io_error)
+    }
+
+    pub fn get(&self, uri: &str) -> Option<&ResumeState> {
+        self.entries.get(uri)
+    }
+
+    // State worth resuming from next time, None if the media should start over
+    pub fn resumable(&self, uri: &str) -> Option<&ResumeState> {
+        self.get(uri).filter(|state| state.is_resumable())
+    }
+
+    // Remember where the media was stopped, or forget it when it was played to the end
+    pub fn record(&mut self, uri: &str, state: ResumeState) {
+        if !state.is_resumable() {
+            self.entries.remove(uri);
+            return;
+        }
+        self.entries.insert(uri.to_string(), state);
+        while self.entries.len() > MAX_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, state)| state.updated)
+                .map(|(uri, _)| uri.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// URI playbin is playing, which changes along a playlist
+pub fn current_uri(playbin: &gst::Element) -> Option<String> {
+    playbin
+        .get_property("current-uri")
+        .ok()?
+        .get::<String>()
+        .ok()
+        .flatten()
+}
+
+// Record the state of playbin in the database, e.g. when the player is closed
+pub fn remember(playbin: &gst::Element, rate: f64) -> Result<()> {
+    let (uri, state) = match (current_uri(playbin), ResumeState::capture(playbin, rate)) {
+        (Some(uri), Some(state)) => (uri, state),
+        _ => return Ok(()),
+    };
+    let mut db = ResumeDb::load();
+    db.record(&uri, state);
+    db.save()
+}
+
+// --resume resumes without asking, --no-resume always starts over, None asks
+pub fn resume_option(args: &Args) -> Option<bool> {
+    if args.has("resume") {
+        Some(true)
+    } else if args.has("no-resume") {
+        Some(false)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,252 @@
+// Seeking with a selectable precision, playback rate and frame stepping
+use gst::prelude::*;
+use std::str::FromStr;
+
+use crate::error::{self, Error, Result};
+use crate::plugins;
+
+// Playback rates offered by the players, reverse playback uses the same rates negated
+pub const RATES: [f64; 9] = [0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 3.0, 4.0];
+pub const MIN_RATE: f64 = 0.25;
+pub const MAX_RATE: f64 = 4.0;
+
+// How a seek lands on the requested position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeekMode {
+    // Nearest keyframe, fast but imprecise
+    KeyUnit,
+    // Exactly the requested position, decoding from the previous keyframe
+    Accurate,
+    // Keyframe before or after the requested position
+    SnapBefore,
+    SnapAfter,
+}
+
+impl Default for SeekMode {
+    fn default() -> SeekMode {
+        SeekMode::KeyUnit
+    }
+}
+
+impl SeekMode {
+    pub const ALL: [SeekMode; 4] = [
+        SeekMode::KeyUnit,
+        SeekMode::Accurate,
+        SeekMode::SnapBefore,
+        SeekMode::SnapAfter,
+    ];
+
+    // Seek flags of this mode, without FLUSH
+    pub fn flags(self) -> gst::SeekFlags {
+        match self {
+            SeekMode::KeyUnit => gst::SeekFlags::KEY_UNIT,
+            SeekMode::Accurate => gst::SeekFlags::ACCURATE,
+            SeekMode::SnapBefore => gst::SeekFlags::KEY_UNIT | gst::SeekFlags::SNAP_BEFORE,
+            SeekMode::SnapAfter => gst::SeekFlags::KEY_UNIT | gst::SeekFlags::SNAP_AFTER,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SeekMode::KeyUnit => "key-unit",
+            SeekMode::Accurate => "accurate",
+            SeekMode::SnapBefore => "snap-before",
+            SeekMode::SnapAfter => "snap-after",
+        }
+    }
+}
+
+impl FromStr for SeekMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SeekMode> {
+        SeekMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| Error::Usage(format!("Unknown seek mode {}", s)))
+    }
+}
+
+// Seek and step state of a player. The direction of the playback is kept to step backward.
+#[derive(Debug, Clone, Copy)]
+pub struct Seeker {
+    pub mode: SeekMode,
+    pub rate: f64,
+}
+
+impl Default for Seeker {
+    fn default() -> Seeker {
+        Seeker {
+            mode: SeekMode::default(),
+            rate: 1.0,
+        }
+    }
+}
+
+impl Seeker {
+    pub fn new(mode: SeekMode) -> Seeker {
+        Seeker {
+            mode,
+            ..Seeker::default()
+        }
+    }
+
+    // Flushing seek to `position` in the current mode, keeping the current rate
+    pub fn seek(&self, element: &gst::Element, position: gst::ClockTime) -> Result<()> {
+        self.seek_with_rate(element, position, self.rate)
+    }
+
+    // Seek `seconds` away from the current position, backward when negative
+    pub fn seek_by(&self, element: &gst::Element, seconds: i64) -> Result<()> {
+        let position = element
+            .query_position::<gst::ClockTime>()
+            .ok_or(Error::Query("position"))?;
+        let duration = element
+            .query_duration::<gst::ClockTime>()
+            .unwrap_or(gst::CLOCK_TIME_NONE);
+        self.seek(element, offset_position(position, duration, seconds))
+    }
+
+    // Send a full seek event, which carries the rate unlike seek_simple
+    fn seek_with_rate(
+        &self,
+        element: &gst::Element,
+        position: gst::ClockTime,
+        rate: f64,
+    ) -> Result<()> {
+        let flags = gst::SeekFlags::FLUSH | self.mode.flags();
+        // Reverse playback plays the segment from its stop down to its start
+        let seek = if rate > 0.0 {
+            gst::Event::new_seek(
+                rate,
+                flags,
+                gst::SeekType::Set,
+                position,
+                gst::SeekType::None,
+                gst::CLOCK_TIME_NONE,
+            )
+        } else {
+            gst::Event::new_seek(
+                rate,
+                flags,
+                gst::SeekType::Set,
+                gst::ClockTime::from_seconds(0),
+                gst::SeekType::Set,
+                position,
+            )
+        };
+        if element.send_event(seek.build()) {
+            Ok(())
+        } else {
+            Err(Error::Seek(format!("to {} at rate {}", position, rate)))
+        }
+    }
+
+    // Change the playback rate from the current position. Negative rates play backward.
+    pub fn set_rate(&mut self, element: &gst::Element, rate: f64) -> Result<()> {
+        if rate.abs() < MIN_RATE || rate.abs() > MAX_RATE {
+            return Err(Error::Seek(format!(
+                "rate {} is out of {}x - {}x",
+                rate, MIN_RATE, MAX_RATE
+            )));
+        }
+        let position = element
+            .query_position::<gst::ClockTime>()
+            .ok_or(Error::Query("position"))?;
+        self.seek_with_rate(element, position, rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+
+    // Next rate of RATES, keeping the direction
+    pub fn faster(&mut self, element: &gst::Element) -> Result<()> {
+        let speed = RATES
+            .iter()
+            .copied()
+            .find(|r| *r > self.rate.abs())
+            .unwrap_or(MAX_RATE);
+        self.set_rate(element, speed.copysign(self.rate))
+    }
+
+    // Previous rate of RATES, keeping the direction
+    pub fn slower(&mut self, element: &gst::Element) -> Result<()> {
+        let speed = RATES
+            .iter()
+            .rev()
+            .copied()
+            .find(|r| *r < self.rate.abs())
+            .unwrap_or(MIN_RATE);
+        self.set_rate(element, speed.copysign(self.rate))
+    }
+
+    pub fn reverse(&mut self, element: &gst::Element) -> Result<()> {
+        self.set_rate(element, -self.rate)
+    }
+
+    // Pause and move by `frames` video frames, backward when negative.
+    // Step events go in the playback direction, so the direction is reversed by a seek first.
+    pub fn step(&mut self, element: &gst::Element, frames: i64) -> Result<()> {
+        if frames == 0 {
+            return Ok(());
+        }
+        error::set_state(element, gst::State::Paused)?;
+
+        let forward = frames > 0;
+        if forward != (self.rate > 0.0) {
+            let position = element
+                .query_position::<gst::ClockTime>()
+                .ok_or(Error::Query("position"))?;
+            let rate = -self.rate;
+            let accurate = Seeker {
+                mode: SeekMode::Accurate,
+                rate,
+            };
+            accurate.seek_with_rate(element, position, rate)?;
+            self.rate = rate;
+        }
+
+        let step = gst::Event::new_step(
+            gst::format::Buffers(Some(frames.abs() as u64)),
+            self.rate.abs(),
+            true,
+            false,
+        )
+        .build();
+        if element.send_event(step) {
+            Ok(())
+        } else {
+            Err(Error::Seek(format!("could not step {} frames", frames)))
+        }
+    }
+}
+
+// `position` moved by `seconds`, staying between 0 and the duration when it is known
+pub fn offset_position(
+    position: gst::ClockTime,
+    duration: gst::ClockTime,
+    seconds: i64,
+) -> gst::ClockTime {
+    let position = position.nseconds().unwrap_or(0);
+    let offset = seconds.abs() as u64 * 1_000_000_000;
+    let target = if seconds < 0 {
+        position.saturating_sub(offset)
+    } else {
+        position.saturating_add(offset)
+    };
+    match duration.nseconds() {
+        Some(duration) => gst::ClockTime::from_nseconds(target.min(duration)),
+        None => gst::ClockTime::from_nseconds(target),
+    }
+}
+
+// Keep the audio pitch when playing faster or slower, by filtering playbin's audio with scaletempo.
+// Must be called before playing. Returns false when scaletempo isn't installed.
+pub fn use_scaletempo(playbin: &gst::Element) -> Result<bool> {
+    if !plugins::find_missing(&["scaletempo"]).is_empty() {
+        return Ok(false);
+    }
+    let scaletempo = error::make_element("scaletempo", None)?;
+    error::set_property(playbin, "audio-filter", &scaletempo)?;
+    Ok(true)
+}
@@ -0,0 +1,75 @@
+// Player settings persisted between runs, as `key=value` lines in the user config directory
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    values: BTreeMap<String, String>,
+}
+
+impl Settings {
+    // $XDG_CONFIG_HOME/gstreamer-rust/settings
+    pub fn default_path() -> PathBuf {
+        glib::get_user_config_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("gstreamer-rust")
+            .join("settings")
+    }
+
+    // Missing or unreadable settings are empty, the defaults are used then
+    pub fn load() -> Settings {
+        Settings::load_from(&Settings::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> Settings {
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let values = content
+            .lines()
+            .filter_map(|line| {
+                let eq = line.find('=')?;
+                Some((
+                    line[..eq].trim().to_string(),
+                    line[eq + 1..].trim().to_string(),
+                ))
+            })
+            .collect();
+        Settings { values }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Settings::default_path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let io_error = |error| Error::Io {
+            path: path.display().to_string(),
+            error,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let content: String = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect();
+        fs::write(path, content).map_err(io_error)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|value| value.as_str())
+    }
+
+    // None if the value is missing or invalid
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.parse().ok()
+    }
+
+    pub fn set<V: ToString>(&mut self, key: &str, value: V) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+}
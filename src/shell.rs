@@ -0,0 +1,577 @@
+// Interactive gst-launch: pipeline descriptions are typed at a prompt and parsed with parse_launch,
+// then the pipeline is controlled while it runs. Bus messages are printed as they arrive.
+//
+//   > launch videotestsrc name=source ! autovideosink
+//   > set source pattern snow
+//   > pads source
+//   > stop
+use gst::prelude::*;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::cli::Args;
+use crate::config::{self, PropertyValue};
+use crate::error::{self, Error, Result};
+use crate::streams;
+
+pub const COMMANDS: &[&str] = &[
+    "launch", "play", "pause", "stop", "set", "get", "elements", "pads", "inspect", "help", "quit",
+];
+
+const HELP: &str = "\
+launch <description>             build a pipeline like gst-launch and play it
+play, pause, stop                change the state of the pipeline
+set <element> <property> <value> set a property of a named element, while running too
+get <element> <property>         print the value of a property
+elements                         list the elements of the pipeline
+pads <element>                   list the pads of an element and their peers
+inspect <factory>                list the properties of an element factory
+help                             print this help
+quit                             stop the pipeline and leave
+A line containing ! is launched directly.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Launch(String),
+    // play, pause and stop
+    State(gst::State),
+    Set {
+        element: String,
+        property: String,
+        value: String,
+    },
+    Get {
+        element: String,
+        property: String,
+    },
+    Elements,
+    Pads(String),
+    Inspect(String),
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Command> {
+        let line = line.trim();
+        let (name, rest) = match line.find(char::is_whitespace) {
+            Some(index) => (&line[..index], line[index..].trim()),
+            None => (line, ""),
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let usage = |usage: &str| Err(Error::Usage(format!("Usage: {}", usage)));
+        match name {
+            "launch" if !rest.is_empty() => Ok(Command::Launch(rest.to_string())),
+            "launch" => usage("launch <description>"),
+            "play" => Ok(Command::State(gst::State::Playing)),
+            "pause" => Ok(Command::State(gst::State::Paused)),
+            "stop" => Ok(Command::State(gst::State::Null)),
+            "set" if words.len() >= 3 => Ok(Command::Set {
+                element: words[0].to_string(),
+                property: words[1].to_string(),
+                // Caps and structures may contain spaces
+                value: words[2..].join(" "),
+            }),
+            "set" => usage("set <element> <property> <value>"),
+            "get" if words.len() == 2 => Ok(Command::Get {
+                element: words[0].to_string(),
+                property: words[1].to_string(),
+            }),
+            "get" => usage("get <element> <property>"),
+            "elements" => Ok(Command::Elements),
+            "pads" if words.len() == 1 => Ok(Command::Pads(words[0].to_string())),
+            "pads" => usage("pads <element>"),
+            "inspect" if words.len() == 1 => Ok(Command::Inspect(words[0].to_string())),
+            "inspect" => usage("inspect <factory>"),
+            "help" | "?" => Ok(Command::Help),
+            "quit" | "exit" => Ok(Command::Quit),
+            // Descriptions can be typed without launch, like the arguments of gst-launch
+            _ if line.contains('!') => Ok(Command::Launch(line.to_string())),
+            _ => Err(Error::Usage(format!(
+                "Unknown command {}, type help for the list of commands",
+                name
+            ))),
+        }
+    }
+}
+
+// Prints the bus messages of a pipeline from another thread, the prompt blocks the main one
+struct BusWatch {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl BusWatch {
+    fn new(bus: gst::Bus) -> BusWatch {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                if let Some(msg) = bus.timed_pop(100 * gst::MSECOND) {
+                    if let Some(text) = describe_message(&msg) {
+                        println!("{}", text);
+                    }
+                }
+            }
+        });
+        BusWatch {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for BusWatch {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// One line for the messages worth showing, None for the others
+pub fn describe_message(msg: &gst::Message) -> Option<String> {
+    let src = msg
+        .get_src()
+        .map(|src| src.get_path_string().to_string())
+        .unwrap_or_default();
+    match msg.view() {
+        gst::MessageView::Error(err) => Some(Error::from_message(err).to_string()),
+        gst::MessageView::Warning(warning) => Some(format!(
+            "Warning from {}: {} ({:?})",
+            src,
+            warning.get_error(),
+            warning.get_debug()
+        )),
+        gst::MessageView::Eos(..) => Some("End of stream".to_string()),
+        // Only the state of the pipeline, every element reports its own
+        gst::MessageView::StateChanged(state)
+            if msg
+                .get_src()
+                .map(|src| src.get_parent().is_none())
+                .unwrap_or(false) =>
+        {
+            Some(format!(
+                "{}: {:?} -> {:?}",
+                src,
+                state.get_old(),
+                state.get_current()
+            ))
+        }
+        gst::MessageView::Buffering(buffering) => {
+            Some(format!("Buffering {}%", buffering.get_percent()))
+        }
+        gst::MessageView::ClockLost(..) => Some("Clock lost".to_string()),
+        gst::MessageView::StreamStart(..) => Some("Stream started".to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+pub struct Shell {
+    pipeline: Option<gst::Element>,
+    watch: Option<BusWatch>,
+}
+
+impl Shell {
+    pub fn new() -> Shell {
+        Shell::default()
+    }
+
+    pub fn pipeline(&self) -> Option<&gst::Element> {
+        self.pipeline.as_ref()
+    }
+
+    // Run a command, returns what should be printed
+    pub fn execute(&mut self, command: &Command) -> Result<String> {
+        match command {
+            Command::Launch(description) => {
+                self.launch(description)?;
+                Ok(String::new())
+            }
+            Command::State(state) => {
+                error::set_state(self.current()?, *state)?;
+                Ok(String::new())
+            }
+            Command::Set {
+                element,
+                property,
+                value,
+            } => {
+                let element = self.element(element)?;
+                config::set_property(&element, property, &PropertyValue::String(value.clone()))?;
+                // Echo what was applied, e.g. the nick of an enum given by number
+                match element.get_property(property) {
+                    Ok(applied) => Ok(format!(
+                        "{} = {}",
+                        property,
+                        streams::value_to_string(&applied)
+                    )),
+                    Err(_) => Ok(String::new()),
+                }
+            }
+            Command::Get { element, property } => {
+                let element = self.element(element)?;
+                element
+                    .get_property(property)
+                    .map(|value| streams::value_to_string(&value))
+                    .map_err(|_| Error::Property {
+                        element: element.get_name().to_string(),
+                        property: property.clone(),
+                    })
+            }
+            Command::Elements => Ok(self
+                .elements()
+                .iter()
+                .map(|element| {
+                    let factory = element
+                        .get_factory()
+                        .map(|factory| factory.get_name().to_string())
+                        .unwrap_or_default();
+                    format!(
+                        "{} ({}) {:?}",
+                        element.get_name(),
+                        factory,
+                        element.get_current_state()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Command::Pads(element) => Ok(describe_pads(&self.element(element)?)),
+            Command::Inspect(factory) => inspect(factory),
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => {
+                self.close()?;
+                Ok(String::new())
+            }
+        }
+    }
+
+    // Replace the pipeline with a new one built from `description`, and play it
+    pub fn launch(&mut self, description: &str) -> Result<()> {
+        let pipeline = gst::parse_launch(description).map_err(Error::Launch)?;
+        self.close()?;
+        self.watch = pipeline.get_bus().map(BusWatch::new);
+        self.pipeline = Some(pipeline);
+        error::set_state(self.current()?, gst::State::Playing).map(|_| ())
+    }
+
+    // Stop the pipeline and forget it
+    pub fn close(&mut self) -> Result<()> {
+        if let Some(pipeline) = self.pipeline.take() {
+            error::set_state(&pipeline, gst::State::Null)?;
+        }
+        // Joined after the pipeline stopped, the last messages are printed
+        self.watch = None;
+        Ok(())
+    }
+
+    // The pipeline itself and every element inside it, the bins included
+    pub fn elements(&self) -> Vec<gst::Element> {
+        let pipeline = match &self.pipeline {
+            Some(pipeline) => pipeline,
+            None => return Vec::new(),
+        };
+        let mut elements = vec![pipeline.clone()];
+        if let Some(bin) = pipeline.downcast_ref::<gst::Bin>() {
+            elements.extend(bin.iterate_recurse().into_iter().filter_map(|e| e.ok()));
+        }
+        elements
+    }
+
+    fn current(&self) -> Result<&gst::Element> {
+        self.pipeline
+            .as_ref()
+            .ok_or_else(|| Error::Usage("No pipeline, type launch <description> first".to_string()))
+    }
+
+    fn element(&self, name: &str) -> Result<gst::Element> {
+        self.current()?;
+        self.elements()
+            .into_iter()
+            .find(|element| element.get_name().as_str() == name)
+            .ok_or_else(|| Error::Usage(format!("No element named {} in the pipeline", name)))
+    }
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+// Direction, caps and peer of every pad of the element
+fn describe_pads(element: &gst::Element) -> String {
+    element
+        .iterate_pads()
+        .into_iter()
+        .filter_map(|pad| pad.ok())
+        .map(|pad| {
+            let caps = pad
+                .get_current_caps()
+                .map(|caps| caps.to_string())
+                .unwrap_or_else(|| "not negotiated".to_string());
+            let peer = pad
+                .get_peer()
+                .map(|peer| format!("linked to {}", peer.get_path_string()))
+                .unwrap_or_else(|| "not linked".to_string());
+            format!(
+                "{} ({:?}) {}, {}",
+                pad.get_name(),
+                pad.get_direction(),
+                peer,
+                caps
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Properties of an element factory with their default value, like a short gst-inspect
+fn inspect(factory: &str) -> Result<String> {
+    let element = error::make_element(factory, None)?;
+    let mut lines = Vec::new();
+    if let Some(factory) = element.get_factory() {
+        lines.push(format!(
+            "{}: {}",
+            factory.get_name(),
+            factory
+                .get_metadata("long-name")
+                .map(|name| name.to_string())
+                .unwrap_or_default()
+        ));
+    }
+    for pspec in element.list_properties() {
+        let name = pspec.get_name().to_string();
+        let value = element
+            .get_property(&name)
+            .map(|value| streams::value_to_string(&value))
+            .unwrap_or_default();
+        lines.push(format!(
+            "  {} ({}): {} [{}]",
+            name,
+            pspec.get_value_type().name(),
+            pspec.get_blurb(),
+            value
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Names of every element factory of the registry, sorted
+pub fn factory_names() -> Vec<String> {
+    let mut names: Vec<String> = gst::Registry::get()
+        .get_feature_list(gst::ElementFactory::static_type())
+        .iter()
+        .map(|feature| feature.get_name().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn property_names(element: &gst::Element) -> Vec<String> {
+    element
+        .list_properties()
+        .iter()
+        .map(|pspec| pspec.get_name().to_string())
+        .collect()
+}
+
+// Tab completion of commands, element factories, element names and property names
+pub struct Completion {
+    factories: Vec<String>,
+    // Properties of the factories completed so far, instanciating elements is not free
+    factory_properties: RefCell<HashMap<String, Vec<String>>>,
+    // Elements of the running pipeline
+    elements: Vec<gst::Element>,
+}
+
+impl Completion {
+    pub fn new() -> Completion {
+        Completion {
+            factories: factory_names(),
+            factory_properties: RefCell::new(HashMap::new()),
+            elements: Vec::new(),
+        }
+    }
+
+    // Called after every command, the pipeline may have been replaced
+    pub fn update(&mut self, shell: &Shell) {
+        self.elements = shell.elements();
+    }
+
+    // Candidates for the word ending at `pos`, and the position where that word starts
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = line[..pos]
+            .rfind(char::is_whitespace)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let word = &line[start..pos];
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let mut candidates = match words.as_slice() {
+            [] => COMMANDS.iter().map(|command| command.to_string()).collect(),
+            ["set"] | ["get"] | ["pads"] => self
+                .elements
+                .iter()
+                .map(|element| element.get_name().to_string())
+                .collect(),
+            ["set", name] | ["get", name] => self
+                .elements
+                .iter()
+                .find(|element| element.get_name().as_str() == *name)
+                .map(property_names)
+                .unwrap_or_default(),
+            ["inspect"] => self.factories.clone(),
+            ["launch", description @ ..] => self.complete_description(description, word),
+            description if line.contains('!') => self.complete_description(description, word),
+            _ => Vec::new(),
+        };
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates.sort();
+        candidates.dedup();
+        (start, candidates)
+    }
+
+    // In a description, a factory starts each element and is followed by its properties
+    fn complete_description(&self, words: &[&str], word: &str) -> Vec<String> {
+        if word.contains('=') {
+            return Vec::new();
+        }
+        let element = words.split(|word| *word == "!").last().unwrap_or(&[]);
+        match element.first() {
+            Some(factory) => {
+                let mut candidates: Vec<String> = self
+                    .properties_of_factory(factory)
+                    .into_iter()
+                    .map(|property| format!("{}=", property))
+                    .collect();
+                candidates.push("!".to_string());
+                candidates
+            }
+            None => self.factories.clone(),
+        }
+    }
+
+    fn properties_of_factory(&self, factory: &str) -> Vec<String> {
+        if let Some(properties) = self.factory_properties.borrow().get(factory) {
+            return properties.clone();
+        }
+        let properties = gst::ElementFactory::make(factory, None)
+            .map(|element| property_names(&element))
+            .unwrap_or_default();
+        self.factory_properties
+            .borrow_mut()
+            .insert(factory.to_string(), properties.clone());
+        properties
+    }
+}
+
+impl Default for Completion {
+    fn default() -> Completion {
+        Completion::new()
+    }
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for Completion {}
+impl Highlighter for Completion {}
+impl Validator for Completion {}
+impl Helper for Completion {}
+
+// $XDG_DATA_HOME/gstreamer-rust/shell_history
+pub fn history_path() -> PathBuf {
+    glib::get_user_data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("gstreamer-rust")
+        .join("shell_history")
+}
+
+pub fn run() -> Result<()> {
+    error::init()?;
+    let args = Args::from_env(&[]);
+
+    let mut shell = Shell::new();
+    // shell <description> launches it right away, like gst-launch
+    if !args.positional.is_empty() {
+        shell.launch(&args.positional.join(" "))?;
+    }
+
+    let mut completion = Completion::new();
+    completion.update(&shell);
+    let mut editor = Editor::<Completion>::new();
+    editor.set_helper(Some(completion));
+    let history = history_path();
+    // There is no history the first time
+    let _ = editor.load_history(&history);
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C clears the line, Ctrl-D leaves
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str());
+
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+        match shell.execute(&command) {
+            Ok(output) if !output.is_empty() => println!("{}", output),
+            Ok(_) => (),
+            Err(err) => eprintln!("{}", err),
+        }
+        if command == Command::Quit {
+            break;
+        }
+        if let Some(completion) = editor.helper_mut() {
+            completion.update(&shell);
+        }
+    }
+
+    if let Some(dir) = history.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(err) = editor.save_history(&history) {
+        eprintln!("Could not save the history: {}", err);
+    }
+    shell.close()
+}
@@ -0,0 +1,302 @@
+// Snapshots of the frame being shown. playbin keeps the last frame given to its video sink in its
+// `sample` property, gst_video::convert_sample encodes it to PNG or JPEG at the requested size.
+use gst::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cli::Args;
+use crate::error::{self, Error, Result};
+use crate::media::MediaSource;
+use crate::plugins;
+
+// How long the conversion of a frame and the preroll of snapshot_at may take
+pub const TIMEOUT_SECONDS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+impl Default for ImageFormat {
+    fn default() -> ImageFormat {
+        ImageFormat::Png
+    }
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 2] = [ImageFormat::Png, ImageFormat::Jpeg];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+
+    fn media_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
+
+    // Encoder convert_sample plugs for this format
+    pub fn encoder(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "pngenc",
+            ImageFormat::Jpeg => "jpegenc",
+        }
+    }
+
+    // Format matching the extension of the file, if any
+    pub fn from_path(path: &Path) -> Option<ImageFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ImageFormat> {
+        ImageFormat::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| Error::Usage(format!("Unknown image format {}", s)))
+    }
+}
+
+// Requested size of the image, a missing dimension keeps the aspect ratio of the frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Size {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl Size {
+    // "320x240", "320x" or "x240"
+    pub fn parse(value: &str) -> Option<Size> {
+        let separator = value.find('x')?;
+        let dimension = |part: &str| -> Option<Option<u32>> {
+            if part.is_empty() {
+                Some(None)
+            } else {
+                part.parse().ok().filter(|&size| size > 0).map(Some)
+            }
+        };
+        Some(Size {
+            width: dimension(&value[..separator])?,
+            height: dimension(&value[separator + 1..])?,
+        })
+    }
+
+    // Size of the image for a frame of `width` x `height` square pixels
+    pub fn scale(self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |size: u32, from: u32, to: u32| {
+            ((u64::from(size) * u64::from(to) + u64::from(from) / 2) / u64::from(from)).max(1)
+                as u32
+        };
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => (w, h),
+            (Some(w), None) => (w, scaled(height, width, w)),
+            (None, Some(h)) => (scaled(width, height, h), h),
+            (None, None) => (width, height),
+        }
+    }
+}
+
+// Frame shown by playbin, None until the first frame reached the video sink
+pub fn current_sample(playbin: &gst::Element) -> Option<gst::Sample> {
+    playbin
+        .get_property("sample")
+        .ok()?
+        .get::<gst::Sample>()
+        .ok()
+        .flatten()
+}
+
+// Encode a raw video sample into `format` at the requested size
+pub fn convert(sample: &gst::Sample, format: ImageFormat, size: Size) -> Result<Vec<u8>> {
+    let structure = sample
+        .get_caps()
+        .and_then(|caps| caps.get_structure(0).map(|structure| structure.to_owned()))
+        .ok_or_else(|| Error::Snapshot("the frame has no caps".to_string()))?;
+    let width = structure.get_some::<i32>("width").unwrap_or(0);
+    let height = structure.get_some::<i32>("height").unwrap_or(0);
+    if width <= 0 || height <= 0 {
+        return Err(Error::Snapshot(format!(
+            "invalid frame size {}x{}",
+            width, height
+        )));
+    }
+    // Pixels of the image are square, the display width of anamorphic frames is used
+    let pixel_aspect_ratio = structure
+        .get_some::<gst::Fraction>("pixel-aspect-ratio")
+        .map(|par| f64::from(*par.numer()) / f64::from(*par.denom()))
+        .unwrap_or(1.0);
+    let display_width = (f64::from(width) * pixel_aspect_ratio).round() as u32;
+    let (width, height) = size.scale(display_width, height as u32);
+
+    let caps = gst::Caps::new_simple(
+        format.media_type(),
+        &[
+            ("width", &(width as i32)),
+            ("height", &(height as i32)),
+            ("pixel-aspect-ratio", &gst::Fraction::new(1, 1)),
+        ],
+    );
+    let image = gst_video::convert_sample(sample, &caps, TIMEOUT_SECONDS * gst::SECOND)
+        .map_err(|err| Error::Snapshot(err.to_string()))?;
+    let buffer = image
+        .get_buffer()
+        .ok_or_else(|| Error::Snapshot("the converted image is empty".to_string()))?;
+    let map = buffer
+        .map_readable()
+        .map_err(|_| Error::Snapshot("could not read the converted image".to_string()))?;
+    Ok(map.as_slice().to_vec())
+}
+
+// Save the frame shown by playbin into `path`, in the format of its extension (PNG by default)
+pub fn save(playbin: &gst::Element, path: &Path, size: Size) -> Result<()> {
+    let format = ImageFormat::from_path(path).unwrap_or_default();
+    plugins::check(&[format.encoder()])?;
+    let sample = current_sample(playbin)
+        .ok_or_else(|| Error::Snapshot("no frame was shown yet".to_string()))?;
+    let image = convert(&sample, format, size)?;
+    fs::write(path, image).map_err(|error| Error::Io {
+        path: path.display().to_string(),
+        error,
+    })
+}
+
+// snapshot-<seconds since the epoch>.png in the pictures directory, or the current one
+pub fn default_location() -> PathBuf {
+    let dir = glib::get_user_special_dir(glib::UserDirectory::Pictures)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    dir.join(format!("snapshot-{}.png", now))
+}
+
+// Command line options of a headless snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub position: gst::ClockTime,
+    pub output: PathBuf,
+    pub size: Size,
+}
+
+impl Options {
+    // --snapshot-at <time> [--snapshot-output <file.png|file.jpg>] [--snapshot-size <width>x<height>]
+    // None when no snapshot was asked, the media is played then.
+    pub fn from_args(args: &Args) -> Result<Option<Options>> {
+        let position = match args.time("snapshot-at")? {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let output = match args.get("snapshot-output") {
+            Some(output) => PathBuf::from(output),
+            None => PathBuf::from(format!("snapshot-{}.png", position.mseconds().unwrap_or(0))),
+        };
+        if ImageFormat::from_path(&output).is_none() {
+            return Err(Error::Usage(format!(
+                "{} is neither a .png nor a .jpg file",
+                output.display()
+            )));
+        }
+        let size = match args.get("snapshot-size") {
+            Some(value) => Size::parse(value).ok_or_else(|| {
+                Error::Usage(format!("Invalid size {} for --snapshot-size", value))
+            })?,
+            None => Size::default(),
+        };
+        Ok(Some(Options {
+            position,
+            output,
+            size,
+        }))
+    }
+}
+
+// Wait until the pipeline prerolled, reporting the error posted on the bus if it failed
+fn wait_preroll(playbin: &gst::Element) -> Result<()> {
+    let (res, _, _) = playbin.get_state(TIMEOUT_SECONDS * gst::SECOND);
+    if res.is_ok() {
+        return Ok(());
+    }
+    let bus = error::bus(playbin)?;
+    if let Some(msg) = bus.pop_filtered(&[gst::MessageType::Error]) {
+        if let gst::MessageView::Error(err) = msg.view() {
+            return Err(Error::from_message(err));
+        }
+    }
+    Err(Error::StateChange {
+        element: playbin.get_name().to_string(),
+        state: gst::State::Paused,
+    })
+}
+
+// Seek the media to `options.position` without playing it, and save the frame there
+pub fn snapshot_at(media: &MediaSource, options: &Options) -> Result<()> {
+    let uri = media
+        .uri()
+        .ok_or_else(|| Error::Usage("Snapshots are taken from a media URI or file".to_string()))?;
+    let format = ImageFormat::from_path(&options.output).unwrap_or_default();
+    plugins::check(&["playbin", "fakesink", format.encoder()])?;
+
+    // Nothing is rendered, the frame is kept by the fakesink
+    let playbin = error::make_element("playbin", None)?;
+    error::set_property(&playbin, "uri", &uri)?;
+    error::set_property(
+        &playbin,
+        "video-sink",
+        &error::make_element("fakesink", None)?,
+    )?;
+    error::set_property(
+        &playbin,
+        "audio-sink",
+        &error::make_element("fakesink", None)?,
+    )?;
+
+    error::set_state(&playbin, gst::State::Paused)?;
+    let res = wait_preroll(&playbin)
+        .and_then(|_| {
+            playbin
+                .seek_simple(
+                    gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                    options.position,
+                )
+                .map_err(|err| Error::Seek(err.to_string()))
+        })
+        .and_then(|_| wait_preroll(&playbin))
+        .and_then(|_| save(&playbin, &options.output, options.size));
+    error::set_state(&playbin, gst::State::Null)?;
+    res
+}
+
+pub fn run(media: &MediaSource, options: &Options) -> Result<()> {
+    error::init()?;
+    snapshot_at(media, options)?;
+    println!(
+        "Saved the frame at {} to {}",
+        options.position,
+        options.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,137 @@
+// Streams found by playbin in the media, with their tags and negotiated caps,
+// and selection of the active audio, video and text stream
+use gst::prelude::*;
+
+use crate::error::{self, Result};
+use crate::tutorial3::StreamKind;
+
+pub const STREAM_KINDS: [StreamKind; 3] = [StreamKind::Video, StreamKind::Audio, StreamKind::Text];
+
+// Name used by playbin in its n-*, current-* properties and get-*-tags, get-*-pad signals
+pub fn playbin_name(kind: StreamKind) -> &'static str {
+    match kind {
+        StreamKind::Video => "video",
+        StreamKind::Audio => "audio",
+        StreamKind::Text => "text",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    pub kind: StreamKind,
+    pub index: i32,
+    // Every tag of the stream, by tag name
+    pub tags: Vec<(String, String)>,
+    // Media type and fields of the negotiated caps, empty until the stream is linked
+    pub caps: Vec<(String, String)>,
+}
+
+impl StreamInfo {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Codec from the tag of this kind of stream
+    pub fn codec(&self) -> Option<&str> {
+        self.tag(match self.kind {
+            StreamKind::Video => "video-codec",
+            StreamKind::Audio => "audio-codec",
+            StreamKind::Text => "subtitle-codec",
+        })
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.tag("language-code")
+    }
+
+    // e.g. "1: Vorbis (en)", to choose the stream
+    pub fn label(&self) -> String {
+        let mut label = format!("{}: {}", self.index, self.codec().unwrap_or("unknown"));
+        if let Some(language) = self.language() {
+            label.push_str(&format!(" ({})", language));
+        }
+        label
+    }
+}
+
+// Streams of a kind, in the order of playbin's indices
+pub fn collect(playbin: &gst::Element, kind: StreamKind) -> Vec<StreamInfo> {
+    let name = playbin_name(kind);
+    let count = playbin
+        .get_property(&format!("n-{}", name))
+        .ok()
+        .and_then(|count| count.get_some::<i32>().ok())
+        .unwrap_or(0);
+    (0..count)
+        .map(|index| StreamInfo {
+            kind,
+            index,
+            tags: stream_tags(playbin, name, index),
+            caps: stream_caps(playbin, name, index),
+        })
+        .collect()
+}
+
+// Index of the active stream of a kind, None if there is none
+pub fn current(playbin: &gst::Element, kind: StreamKind) -> Option<i32> {
+    playbin
+        .get_property(&format!("current-{}", playbin_name(kind)))
+        .ok()?
+        .get_some::<i32>()
+        .ok()
+        .filter(|index| *index >= 0)
+}
+
+// Switch the active stream of a kind
+pub fn select(playbin: &gst::Element, kind: StreamKind, index: i32) -> Result<()> {
+    error::set_property(playbin, &format!("current-{}", playbin_name(kind)), &index)
+}
+
+fn stream_tags(playbin: &gst::Element, name: &str, index: i32) -> Vec<(String, String)> {
+    let tags = playbin
+        .emit(&format!("get-{}-tags", name), &[&index])
+        .ok()
+        .flatten()
+        .and_then(|tags| tags.get::<gst::TagList>().ok().flatten());
+    match tags {
+        Some(tags) => tags
+            .iter()
+            .map(|(tag, value)| (tag.to_string(), value_to_string(&value)))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn stream_caps(playbin: &gst::Element, name: &str, index: i32) -> Vec<(String, String)> {
+    let caps = playbin
+        .emit(&format!("get-{}-pad", name), &[&index])
+        .ok()
+        .flatten()
+        .and_then(|pad| pad.get::<gst::Pad>().ok().flatten())
+        .and_then(|pad| pad.get_current_caps());
+    let structure = match caps.as_ref().and_then(|caps| caps.get_structure(0)) {
+        Some(structure) => structure,
+        None => return Vec::new(),
+    };
+    let mut fields = vec![("media".to_string(), structure.get_name().to_string())];
+    fields.extend(
+        structure
+            .iter()
+            .map(|(field, value)| (field.to_string(), value_to_string(value))),
+    );
+    fields
+}
+
+// Strings as they are, anything else (fractions, dates, buffers...) serialized by GStreamer
+pub fn value_to_string(value: &glib::Value) -> String {
+    if let Ok(Some(string)) = value.get::<String>() {
+        return string;
+    }
+    value
+        .serialize()
+        .map(|string| string.to_string())
+        .unwrap_or_else(|| format!("{:?}", value))
+}
@@ -0,0 +1,76 @@
+// Subtitles of playbin: external subtitle files, rendering toggle, font and track selection
+use gst::prelude::*;
+use std::path::Path;
+
+use crate::error::{self, Error, Result};
+use crate::media;
+use crate::player;
+use crate::streams;
+use crate::tutorial3::StreamKind;
+
+// Subtitle files playbin can load through suburi
+pub const EXTENSIONS: &[&str] = &["srt", "vtt", "ass", "ssa", "sub"];
+
+pub fn is_subtitle_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Use an external subtitle file, given as a path or an URI. playbin reads suburi when it starts,
+// so this must be called before playing, see load_external otherwise.
+pub fn set_external(playbin: &gst::Element, location: &str) -> Result<()> {
+    error::set_property(playbin, "suburi", &media::to_uri(location))
+}
+
+// Load an external subtitle file while playing.
+// Returns the position to seek to once playbin is prerolled again.
+pub fn load_external(playbin: &gst::Element, location: &str) -> Result<Option<gst::ClockTime>> {
+    player::reconfigure(playbin, || set_external(playbin, location))
+}
+
+// Font of the subtitles as a Pango font description, e.g. "Sans Bold 24"
+pub fn set_font(playbin: &gst::Element, font_desc: &str) -> Result<()> {
+    error::set_property(playbin, "subtitle-font-desc", &font_desc)
+}
+
+// Whether the text flag of playbin is set, subtitles are only rendered with it
+pub fn is_enabled(playbin: &gst::Element) -> bool {
+    playbin
+        .get_property("flags")
+        .ok()
+        .and_then(|flags| {
+            glib::FlagsClass::new(flags.type_()).map(|class| class.is_set_by_nick(&flags, "text"))
+        })
+        .unwrap_or(false)
+}
+
+pub fn set_enabled(playbin: &gst::Element, enabled: bool) -> Result<()> {
+    let flags_error = || Error::Property {
+        element: playbin.get_name().to_string(),
+        property: "flags".to_string(),
+    };
+    let flags = playbin.get_property("flags").map_err(|_| flags_error())?;
+    let class = glib::FlagsClass::new(flags.type_()).ok_or_else(flags_error)?;
+    let builder = class.builder_with_value(flags).ok_or_else(flags_error)?;
+    let builder = if enabled {
+        builder.set_by_nick("text")
+    } else {
+        builder.unset_by_nick("text")
+    };
+    let flags = builder.build().ok_or_else(flags_error)?;
+    error::set_property(playbin, "flags", &flags)
+}
+
+// Render the given text track, or no subtitles at all with None
+pub fn select_track(playbin: &gst::Element, index: Option<i32>) -> Result<()> {
+    match index {
+        Some(index) => {
+            streams::select(playbin, StreamKind::Text, index)?;
+            set_enabled(playbin, true)
+        }
+        None => set_enabled(playbin, false),
+    }
+}
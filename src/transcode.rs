@@ -0,0 +1,347 @@
+// Transcode a media into a file: uridecodebin decodes it like in tutorial3, encodebin encodes the
+// decoded streams with the container and codecs of an encoding profile, filesink writes the result.
+use gst::prelude::*;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::cli::{self, Args};
+use crate::error::{self, Error, Result};
+use crate::media::{self, MediaSource};
+use crate::plugins::{self, MissingPlugin};
+use crate::tutorial3::StreamKind;
+
+// Container and codecs of the output file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Profile {
+    // WebM with VP8 and Vorbis
+    WebM,
+    // Matroska with VP8 and Opus
+    Matroska,
+    // Ogg with Theora and Opus
+    Ogg,
+    // MP4 with H.264 (x264) and AAC (libav)
+    Mp4,
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::WebM
+    }
+}
+
+impl Profile {
+    pub const ALL: [Profile; 4] = [Profile::WebM, Profile::Matroska, Profile::Ogg, Profile::Mp4];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::WebM => "webm",
+            Profile::Matroska => "matroska",
+            Profile::Ogg => "ogg",
+            Profile::Mp4 => "mp4",
+        }
+    }
+
+    // Usual extension of the output file
+    pub fn extension(self) -> &'static str {
+        match self {
+            Profile::WebM => "webm",
+            Profile::Matroska => "mkv",
+            Profile::Ogg => "ogg",
+            Profile::Mp4 => "mp4",
+        }
+    }
+
+    // Profile matching the extension of the output file, if any
+    pub fn from_path(path: &Path) -> Option<Profile> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Profile::ALL
+            .iter()
+            .copied()
+            .find(|profile| profile.extension() == extension)
+    }
+
+    // Muxer and encoders encodebin will pick for this profile, for the preflight check
+    pub fn required_elements(self) -> &'static [&'static str] {
+        match self {
+            Profile::WebM => &["webmmux", "vp8enc", "vorbisenc"],
+            Profile::Matroska => &["matroskamux", "vp8enc", "opusenc"],
+            Profile::Ogg => &["oggmux", "theoraenc", "opusenc"],
+            Profile::Mp4 => &["mp4mux", "x264enc", "avenc_aac"],
+        }
+    }
+
+    // Profiles whose encoders and muxer are installed. GStreamer must be initialized.
+    pub fn available() -> Vec<Profile> {
+        Profile::ALL
+            .iter()
+            .copied()
+            .filter(|profile| plugins::find_missing(profile.required_elements()).is_empty())
+            .collect()
+    }
+
+    fn container_caps(self) -> gst::Caps {
+        match self {
+            Profile::WebM => gst::Caps::new_simple("video/webm", &[]),
+            Profile::Matroska => gst::Caps::new_simple("video/x-matroska", &[]),
+            Profile::Ogg => gst::Caps::new_simple("application/ogg", &[]),
+            Profile::Mp4 => gst::Caps::new_simple("video/quicktime", &[("variant", &"iso")]),
+        }
+    }
+
+    fn video_caps(self) -> gst::Caps {
+        match self {
+            Profile::WebM | Profile::Matroska => gst::Caps::new_simple("video/x-vp8", &[]),
+            Profile::Ogg => gst::Caps::new_simple("video/x-theora", &[]),
+            Profile::Mp4 => gst::Caps::new_simple("video/x-h264", &[]),
+        }
+    }
+
+    fn audio_caps(self) -> gst::Caps {
+        match self {
+            Profile::WebM => gst::Caps::new_simple("audio/x-vorbis", &[]),
+            Profile::Matroska | Profile::Ogg => gst::Caps::new_simple("audio/x-opus", &[]),
+            Profile::Mp4 => gst::Caps::new_simple("audio/mpeg", &[("mpegversion", &4i32)]),
+        }
+    }
+
+    // Encoding profile given to encodebin. Both streams are optional (presence 0),
+    // so media without audio or without video are transcoded too.
+    pub fn encoding_profile(self) -> Result<gst_pbutils::EncodingContainerProfile> {
+        let profile_error = |err: &dyn std::fmt::Display| {
+            Error::Config(format!("{} profile: {}", self.name(), err))
+        };
+        let video_caps = self.video_caps();
+        let video = gst_pbutils::EncodingVideoProfileBuilder::new()
+            .format(&video_caps)
+            .presence(0)
+            .build()
+            .map_err(|err| profile_error(&err))?;
+        let audio_caps = self.audio_caps();
+        let audio = gst_pbutils::EncodingAudioProfileBuilder::new()
+            .format(&audio_caps)
+            .presence(0)
+            .build()
+            .map_err(|err| profile_error(&err))?;
+        let container_caps = self.container_caps();
+        gst_pbutils::EncodingContainerProfileBuilder::new()
+            .name(self.name())
+            .format(&container_caps)
+            .add_profile(&video)
+            .add_profile(&audio)
+            .build()
+            .map_err(|err| profile_error(&err))
+    }
+}
+
+impl FromStr for Profile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Profile> {
+        Profile::ALL
+            .iter()
+            .copied()
+            .find(|profile| profile.name() == s)
+            .ok_or_else(|| Error::Usage(format!("Unknown profile {}", s)))
+    }
+}
+
+// Command line options of the transcode binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub media: MediaSource,
+    pub output: PathBuf,
+    pub profile: Profile,
+}
+
+impl Options {
+    // transcode <media> <output> [--profile webm|matroska|ogg|mp4]
+    // Without --profile, the profile is picked from the extension of the output file.
+    pub fn from_args(args: &Args) -> Result<Options> {
+        let (media, output) = match args.positional.as_slice() {
+            [media, output] => (MediaSource::parse(media), PathBuf::from(output)),
+            _ => {
+                return Err(Error::Usage(
+                    "Usage: transcode <media> <output> [--profile webm|matroska|ogg|mp4]"
+                        .to_string(),
+                ))
+            }
+        };
+        let profile = match args.parse_value("profile")? {
+            Some(profile) => profile,
+            None => Profile::from_path(&output).unwrap_or_default(),
+        };
+        Ok(Options {
+            media,
+            output,
+            profile,
+        })
+    }
+}
+
+// Elements instanciated by build_pipeline, for the preflight check of missing plugins
+pub fn required_elements(media: &MediaSource, profile: Profile) -> Vec<&'static str> {
+    let mut elements = media.source_elements().to_vec();
+    elements.extend(&["encodebin", "filesink"]);
+    elements.extend(profile.required_elements());
+    elements
+}
+
+// Instanciate the pipeline transcoding `media` into the file `output`
+pub fn build_pipeline(
+    media: &MediaSource,
+    output: &Path,
+    profile: Profile,
+) -> Result<gst::Pipeline> {
+    build_pipeline_with_source(media.make_source(Some("source"))?, output, profile)
+}
+
+// Same as build_pipeline with a source made by the caller, e.g. a test source limited in length
+pub fn build_pipeline_with_source(
+    source: gst::Element,
+    output: &Path,
+    profile: Profile,
+) -> Result<gst::Pipeline> {
+    let encodebin = error::make_element("encodebin", Some("encoder"))?;
+    error::set_property(&encodebin, "profile", &profile.encoding_profile()?)?;
+    let sink = error::make_element("filesink", Some("sink"))?;
+    error::set_property(&sink, "location", &output.display().to_string())?;
+
+    let pipeline = gst::Pipeline::new(Some("transcode-pipeline"));
+    error::add_many(pipeline.upcast_ref(), &[&source, &encodebin, &sink])?;
+    error::link_many(&[&encodebin, &sink])?;
+
+    // Decoded streams are linked to a sink pad encodebin requests for their caps
+    let encodebin_weak = encodebin.downgrade();
+    media::connect_src_pads(&source, move |src_pad| {
+        let encodebin = match encodebin_weak.upgrade() {
+            Some(encodebin) => encodebin,
+            None => return,
+        };
+        let caps = match src_pad
+            .get_current_caps()
+            .or_else(|| src_pad.query_caps(None))
+        {
+            Some(caps) => caps,
+            None => return,
+        };
+        let kind = caps
+            .get_structure(0)
+            .and_then(|structure| StreamKind::from_caps_name(structure.get_name()));
+        if kind != Some(StreamKind::Audio) && kind != Some(StreamKind::Video) {
+            println!("Not transcoding the stream {}", caps);
+            return;
+        }
+        let sink_pad = encodebin
+            .emit("request-pad", &[&caps])
+            .ok()
+            .flatten()
+            .and_then(|pad| pad.get::<gst::Pad>().ok().flatten());
+        let res = match sink_pad {
+            Some(sink_pad) => error::link_pads(src_pad, &sink_pad),
+            None => Err(Error::Link {
+                src: src_pad.get_path_string().to_string(),
+                sink: encodebin.get_name().to_string(),
+            }),
+        };
+        if let Err(err) = res {
+            eprintln!("{}", err);
+        }
+    });
+
+    Ok(pipeline)
+}
+
+// Percentage of the media transcoded so far, None until both are known
+pub fn progress(position: gst::ClockTime, duration: gst::ClockTime) -> Option<f64> {
+    match (position.nseconds(), duration.nseconds()) {
+        (Some(position), Some(duration)) if duration > 0 => {
+            Some((position as f64 * 100.0 / duration as f64).min(100.0))
+        }
+        _ => None,
+    }
+}
+
+// How often the progress is printed while transcoding
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+// Run the pipeline until EOS, printing the progress twice a second however busy the bus is
+pub fn transcode(pipeline: &gst::Pipeline) -> Result<()> {
+    error::set_state(pipeline, gst::State::Playing)?;
+
+    let bus = error::bus(pipeline)?;
+    let mut res = Ok(());
+    let mut missing = Vec::new();
+    let mut next_progress = Instant::now() + PROGRESS_INTERVAL;
+    loop {
+        let timeout = next_progress.saturating_duration_since(Instant::now());
+        let msg = bus.timed_pop(gst::ClockTime::from_nseconds(timeout.as_nanos() as u64));
+        if Instant::now() >= next_progress {
+            print_progress(pipeline);
+            next_progress = Instant::now() + PROGRESS_INTERVAL;
+        }
+        let msg = match msg {
+            Some(msg) => msg,
+            None => continue,
+        };
+        match msg.view() {
+            gst::MessageView::Error(err) => {
+                res = Err(plugins::error_with_missing(
+                    Error::from_message(err),
+                    std::mem::take(&mut missing),
+                ));
+                break;
+            }
+            gst::MessageView::Element(..) => {
+                // uridecodebin can't decode a stream, or encodebin found no encoder
+                if let Some(plugin) = MissingPlugin::from_message(&msg) {
+                    eprintln!("Missing plugin: {}", plugin);
+                    missing.push(plugin);
+                }
+            }
+            gst::MessageView::Eos(..) => {
+                print_progress(pipeline);
+                break;
+            }
+            _ => (),
+        }
+    }
+    println!();
+
+    error::set_state(pipeline, gst::State::Null)?;
+    res
+}
+
+fn print_progress(pipeline: &gst::Pipeline) {
+    let position = pipeline
+        .query_position::<gst::ClockTime>()
+        .unwrap_or(gst::CLOCK_TIME_NONE);
+    let duration = pipeline
+        .query_duration::<gst::ClockTime>()
+        .unwrap_or(gst::CLOCK_TIME_NONE);
+    let percent = match progress(position, duration) {
+        Some(percent) => format!(" ({:.0}%)", percent),
+        None => String::new(),
+    };
+    print!(
+        "\rTranscoded {} / {}{}",
+        cli::format_time(position),
+        cli::format_time(duration),
+        percent
+    );
+    let _ = io::stdout().flush();
+}
+
+pub fn run(options: &Options) -> Result<()> {
+    error::init()?;
+    plugins::check(&required_elements(&options.media, options.profile))?;
+
+    let pipeline = build_pipeline(&options.media, &options.output, options.profile)?;
+    println!(
+        "Transcoding to {} ({})",
+        options.output.display(),
+        options.profile.name()
+    );
+    transcode(&pipeline)
+}
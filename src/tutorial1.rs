@@ -1,29 +1,36 @@
-// extern crate gstreamer as gst;
-// use gst::prelude::*;
-
-
-// fn main(){
-//         // Initialize GStreamer
-//         gst::init().unwrap();
-
-//         // Build the pipeline
-//         let uri =
-//             "https://www.freedesktop.org/software/gstreamer-sdk/data/media/sintel_trailer-480p.webm";
-//         let pipeline = gst::parse_launch(&format!("playbin uri={}", uri)).unwrap();
-    
-//         // Start playing
-//         pipeline
-//             .set_state(gst::State::Playing)
-//             .expect("Unable to set the pipeline to the `Playing` state");
-    
-//         // Wait until error or EOS
-//         let bus = pipeline.get_bus().unwrap();
-//         let msg_types = [gst::MessageType::Error,gst::MessageType::Eos];
-//         bus.timed_pop_filtered(gst::ClockTime::from_mseconds(1000), &msg_types);
-    
-//         // Shutdown pipeline
-//         pipeline
-//             .set_state(gst::State::Null)
-//             .expect("Unable to set the pipeline to the `Null` state");
-    
-// }
\ No newline at end of file
+// Tutorial 1: Play a media with playbin and wait until an error or EOS
+use gst::prelude::*;
+
+use crate::error::{self, Error, Result};
+use crate::media::MediaSource;
+use crate::plugins;
+
+// Build the whole playback pipeline with a single playbin
+pub fn build_pipeline(media: &MediaSource) -> Result<gst::Element> {
+    media.make_player(None)
+}
+
+pub fn run(media: &MediaSource) -> Result<()> {
+    // Initialize GStreamer
+    error::init()?;
+    plugins::check(media.player_elements())?;
+
+    // Build the pipeline
+    let pipeline = build_pipeline(media)?;
+
+    // Start playing
+    error::set_state(&pipeline, gst::State::Playing)?;
+
+    // Wait until error or EOS
+    let bus = error::bus(&pipeline)?;
+    let msg_types = [gst::MessageType::Error, gst::MessageType::Eos];
+    let msg = bus.timed_pop_filtered(gst::ClockTime::from_mseconds(1000), &msg_types);
+
+    // Shutdown pipeline
+    error::set_state(&pipeline, gst::State::Null)?;
+
+    match msg.as_ref().map(|msg| msg.view()) {
+        Some(gst::MessageView::Error(err)) => Err(Error::from_message(err)),
+        _ => Ok(()),
+    }
+}
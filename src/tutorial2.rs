@@ -1,62 +1,67 @@
-// extern crate gstreamer as gst;
-// use gst::prelude::*;
-
-// fn main(){
-//     // Initialize pipeline
-//     gst::init().unwrap();
-
-//     // Instanciating source and sink elements
-//     let source = gst::ElementFactory::make("videotestsrc", Some("source")).expect("Could not create a source element");
-//     let sink = gst::ElementFactory::make("autovideosink",Some("sink")).expect("Could not create a sink element");
-
-//     // Instanciate pipeline
-//     let pipeline = gst::Pipeline::new(Some("test-manual-pipeline"));
-
-//     // Connecting each elements
-//     pipeline.add_many(&[&source,&sink]).unwrap();
-//     source.link(&sink).expect("Elements could not be linked");
-
-//     // Set a property
-//     source.set_property_from_str("pattern", "smpte");
-//     source.set_property(property_name, value)
-
-//     // Start playing pipeline
-//     pipeline.set_state(gst::State::Playing).expect("Unable to set the pipeline to Playing state");
-
-//     // Watch pipeline until Eos or getting an error
-//     let bus = pipeline.get_bus().unwrap();
-//     for msg in bus.iter_timed(gst::ClockTime::from_mseconds(3000)){
-//         match msg.view() {
-//             gst::MessageView::Error(err)=>{
-//                 eprintln!(
-//                     "Error received from element {:?}: {}",
-//                     err.get_src().map(|s| s.get_path_string()),
-//                     err.get_error()
-//                 );
-//                 eprintln!("Debugging information: {:?}", err.get_debug());
-//                 break;
-//             }
-//             gst::MessageView::Eos(..)=>break,
-//             _=>(),
-//         }
-//     }
-//     source.set_property_from_str("pattern", "snow");
-//     for msg in bus.iter_timed(gst::ClockTime::from_mseconds(3000)){
-//         match msg.view() {
-//             gst::MessageView::Error(err)=>{
-//                 eprintln!(
-//                     "Error received from element {:?}: {}",
-//                     err.get_src().map(|s| s.get_path_string()),
-//                     err.get_error()
-//                 );
-//                 eprintln!("Debugging information: {:?}", err.get_debug());
-//                 break;
-//             }
-//             gst::MessageView::Eos(..)=>break,
-//             _=>(),
-//         }
-//     }
-
-//     // Cleaning up
-//     pipeline.set_state(gst::State::Null).expect("Unable to set the pipeline to `Null` state");
-// }
\ No newline at end of file
+// Tutorial 2: Build a videotestsrc ! autovideosink pipeline by hand and change a property while playing
+use gst::prelude::*;
+
+use crate::error::{self, Error, Result};
+use crate::plugins;
+
+// Elements instanciated by build_pipeline
+pub const REQUIRED_ELEMENTS: &[&str] = &["videotestsrc", "autovideosink"];
+
+// Instanciate and link the elements. The source is returned to change its properties later.
+pub fn build_pipeline() -> Result<(gst::Pipeline, gst::Element)> {
+    let sink = error::make_element("autovideosink", Some("sink"))?;
+    build_pipeline_with_sink(sink)
+}
+
+// Same as build_pipeline, but rendering into the given sink (e.g. fakesink on headless machines)
+pub fn build_pipeline_with_sink(sink: gst::Element) -> Result<(gst::Pipeline, gst::Element)> {
+    // Instanciating source element
+    let source = error::make_element("videotestsrc", Some("source"))?;
+
+    // Instanciate pipeline
+    let pipeline = gst::Pipeline::new(Some("test-manual-pipeline"));
+
+    // Connecting each elements
+    error::add_many(pipeline.upcast_ref(), &[&source, &sink])?;
+    error::link_many(&[&source, &sink])?;
+
+    Ok((pipeline, source))
+}
+
+pub fn run() -> Result<()> {
+    // Initialize pipeline
+    error::init()?;
+    plugins::check(REQUIRED_ELEMENTS)?;
+
+    let (pipeline, source) = build_pipeline()?;
+
+    // Set a property
+    source.set_property_from_str("pattern", "smpte");
+
+    // Start playing pipeline
+    error::set_state(&pipeline, gst::State::Playing)?;
+
+    // Watch pipeline until Eos or getting an error
+    let bus = error::bus(&pipeline)?;
+    let res = watch_bus(&bus, gst::ClockTime::from_mseconds(3000)).and_then(|_| {
+        // Change the pattern while playing
+        source.set_property_from_str("pattern", "snow");
+        watch_bus(&bus, gst::ClockTime::from_mseconds(3000))
+    });
+
+    // Cleaning up
+    error::set_state(&pipeline, gst::State::Null)?;
+    res
+}
+
+// Watch the bus until Eos, an error or the timeout
+fn watch_bus(bus: &gst::Bus, timeout: gst::ClockTime) -> Result<()> {
+    for msg in bus.iter_timed(timeout) {
+        match msg.view() {
+            gst::MessageView::Error(err) => return Err(Error::from_message(err)),
+            gst::MessageView::Eos(..) => break,
+            _ => (),
+        }
+    }
+    Ok(())
+}
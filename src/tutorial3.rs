@@ -1,94 +1,255 @@
-extern crate gstreamer as gst;
+// Tutorial 3: Link the pads of uridecodebin dynamically when they appear
 use gst::prelude::*;
 
-fn main(){
-    gst::init().unwrap();
+use crate::buffering::{self, Buffering};
+use crate::error::{self, Error, Result};
+use crate::media::{self, MediaSource};
+use crate::plugins::{self, MissingPlugin};
 
-    // Instanciate elements in pipeline
-    let source = gst::ElementFactory::make("uridecodebin", Some("source")).expect("Could not instanciate uridecodebin");
-    let convert = gst::ElementFactory::make("audioconvert", Some("convert")).expect("Could not instanciate audioconvert");
-    let sink = gst::ElementFactory::make("autoaudiosink", Some("sink")).expect("Could not instanciate audiosink");
+// Kind of the decoded stream exposed on a src pad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamKind {
+    Audio,
+    Video,
+    Text,
+}
 
-    // Instanciate pipeline
-    let pipeline = gst::Pipeline::new(Some("test-pipeline"));
+impl StreamKind {
+    // Find the kind of a stream from the name of its caps structure, None for unsupported streams
+    pub fn from_caps_name(name: &str) -> Option<StreamKind> {
+        if name.starts_with("audio/x-raw") {
+            Some(StreamKind::Audio)
+        } else if name.starts_with("video/x-raw") {
+            Some(StreamKind::Video)
+        } else if name.starts_with("text/x-raw") {
+            Some(StreamKind::Text)
+        } else {
+            None
+        }
+    }
+}
+
+// Sinks used by build_pipeline. Subtitles are not rendered, they are just consumed.
+pub fn default_sink(kind: StreamKind) -> Result<gst::Element> {
+    match kind {
+        StreamKind::Audio => error::make_element("autoaudiosink", None),
+        StreamKind::Video => error::make_element("autovideosink", None),
+        StreamKind::Text => error::make_element("fakesink", None),
+    }
+}
+
+// Elements instanciated by build_pipeline, for the preflight check of missing plugins
+pub fn required_elements(media: &MediaSource) -> Vec<&'static str> {
+    let mut elements = media.source_elements().to_vec();
+    elements.extend(&[
+        "queue",
+        "audioconvert",
+        "audioresample",
+        "autoaudiosink",
+        "videoconvert",
+        "videoscale",
+        "autovideosink",
+        "fakesink",
+    ]);
+    elements
+}
+
+// Instanciate the pipeline. A branch is added for every audio, video or text pad of the source.
+pub fn build_pipeline(media: &MediaSource) -> Result<gst::Pipeline> {
+    build_pipeline_with_sinks(media, default_sink)
+}
 
-    // Add all elements inside of the pipeline
-    pipeline.add_many(&[&source,&convert,&sink]).unwrap();
-    convert.link(&sink).expect("element could not be linked");
-    // It is impossible to link with source and convert here.
+// Same as build_pipeline, but `make_sink` creates the sink of each branch (e.g. fakesink on headless machines)
+pub fn build_pipeline_with_sinks<F>(media: &MediaSource, make_sink: F) -> Result<gst::Pipeline>
+where
+    F: Fn(StreamKind) -> Result<gst::Element> + Send + Sync + 'static,
+{
+    // Instanciate source element
+    let source = media.make_source(Some("source"))?;
 
-    // Set uri property in uridecodebin
-    let uri = "https://www.freedesktop.org/software/gstreamer-sdk/data/media/sintel_trailer-480p.webm";
-    source.set_property("uri",&uri).expect("Couldn't set uri property on uridecodebin");
+    // Instanciate pipeline
+    let pipeline = gst::Pipeline::new(Some("test-pipeline"));
+    error::add_many(pipeline.upcast_ref(), &[&source])?;
+    // It is impossible to link the source here, its pads don't exist yet.
 
     // Initiate weak pointer to be used in different thread
     let pipeline_weak = pipeline.downgrade();
-    let convert_weak = convert.downgrade();
-    
-    // Add event listener
-    source.connect_pad_added(move |_,src_pad|{
+
+    // Add event listener. Synthetic sources have a static pad, it is handled immediately.
+    media::connect_src_pads(&source, move |src_pad| {
         // Getting actual reference from weak reference if it was not discarded
         let pipeline = match pipeline_weak.upgrade() {
-            Some(pipeline)=>pipeline,
-            None=>return,
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+        println!(
+            "Received new pad {} from {}",
+            src_pad.get_name(),
+            pipeline.get_name()
+        );
+
+        // Retrive capability of the elements
+        let new_pad_caps = match src_pad
+            .get_current_caps()
+            .or_else(|| src_pad.query_caps(None))
+        {
+            Some(caps) => caps,
+            None => {
+                eprintln!("Failed to get caps of new pad {}", src_pad.get_name());
+                return;
+            }
+        };
+        let new_pad_type = match new_pad_caps.get_structure(0) {
+            Some(new_pad_struct) => new_pad_struct.get_name(),
+            None => {
+                eprintln!("Caps of new pad {} are empty", src_pad.get_name());
+                return;
+            }
+        };
+
+        // Check this pad is for audio, video or subtitles
+        let kind = match StreamKind::from_caps_name(new_pad_type) {
+            Some(kind) => kind,
+            None => {
+                println!(
+                    "It has type {} which is not supported.Ignoreing",
+                    new_pad_type
+                );
+                return;
+            }
         };
-        let convert = match convert_weak.upgrade() {
-            Some(convert)=>convert,
-            None=>return,
+
+        // Build a new branch for this pad and link the src pad to it
+        let sink_pad = match add_branch(&pipeline, kind, &make_sink) {
+            Ok(sink_pad) => sink_pad,
+            Err(err) => {
+                println!(
+                    "Could not build a branch for type {}: {}",
+                    new_pad_type, err
+                );
+                return;
+            }
         };
-        println!("Received new pad {} from {}",src_pad.get_name(),pipeline.get_name());
+        match error::link_pads(src_pad, &sink_pad) {
+            Ok(()) => println!("Link succeeded type {}", new_pad_type),
+            Err(err) => println!("Type is {} but link failed: {}", new_pad_type, err),
+        }
+    });
+
+    Ok(pipeline)
+}
 
-        // Obtain the sink_pad from audioconvert element
-        let sink_pad = convert.get_static_pad("sink").expect("Failed to get static sink pad from convert");
-        if sink_pad.is_linked() {
-            println!("We are already linked. Ignoreing");
-            return;
+// Add queue ! convert ! (scale or resample) ! sink to the pipeline and return the sink pad of the queue.
+// The branch is brought to the state of the pipeline, since it can be added while playing.
+fn add_branch<F>(pipeline: &gst::Pipeline, kind: StreamKind, make_sink: &F) -> Result<gst::Pad>
+where
+    F: Fn(StreamKind) -> Result<gst::Element>,
+{
+    let mut elements = vec![error::make_element("queue", None)?];
+    match kind {
+        StreamKind::Audio => {
+            elements.push(error::make_element("audioconvert", None)?);
+            elements.push(error::make_element("audioresample", None)?);
         }
-        
-        // Retrive capability of the elements
-        let new_pad_caps = src_pad.get_current_caps().expect("Failed to get caps of new pad");
-        let new_pad_struct  = new_pad_caps.get_structure(0).expect("Failed to get first structure of caps");
-        let new_pad_type = new_pad_struct.get_name();
-        
-        // Check this pad is for audio, otherwise, it should be for video
-        let is_audio = new_pad_type.starts_with("audio/x-raw");
-        if !is_audio{
-            println!("It has type {} which is not a raw audio.Ignoreing",new_pad_type);
-            return;
+        StreamKind::Video => {
+            elements.push(error::make_element("videoconvert", None)?);
+            elements.push(error::make_element("videoscale", None)?);
         }
+        StreamKind::Text => (),
+    }
+    elements.push(make_sink(kind)?);
+
+    let elements: Vec<&gst::Element> = elements.iter().collect();
+    error::add_many(pipeline.upcast_ref(), &elements)?;
+    error::link_many(&elements)?;
+    for element in &elements {
+        element
+            .sync_state_with_parent()
+            .map_err(|_| Error::StateChange {
+                element: element.get_name().to_string(),
+                state: pipeline.get_current_state(),
+            })?;
+    }
+
+    error::static_pad(elements[0], "sink")
+}
 
-        // Link the src pad to sink pad
-        let res = src_pad.link(&sink_pad);
-        if res.is_err() {
-            println!("Type is {} but link failed",new_pad_type);
-        }else{
-            println!("Link succeeded type {}",new_pad_type)
+pub fn run(media: &MediaSource) -> Result<()> {
+    error::init()?;
+    plugins::check(&required_elements(media))?;
+
+    let pipeline = build_pipeline(media)?;
+    // Network streams are buffered, the pipeline waits paused until the buffer is full
+    if let Some(source) = pipeline.get_by_name("source") {
+        if source.find_property("use-buffering").is_some() {
+            error::set_property(&source, "use-buffering", &true)?;
         }
-    });
+    }
 
     // Make pipeline state Playing
-    pipeline.set_state(gst::State::Playing).expect("Failed to set the pipeline to the playing state");
+    let mut buffering = Buffering::default();
+    buffering::request_state(pipeline.upcast_ref(), &mut buffering, gst::State::Playing)?;
+    if buffering.is_live() {
+        println!("Live source, playing without buffering");
+    }
 
     // Obtain the bus and loop while monitor the messages
-    let bus = pipeline.get_bus().unwrap();
-    for msg in bus.iter_timed(gst::CLOCK_TIME_NONE){
-        use gst::MessageView;
-        match msg.view(){
-            gst::MessageView::Error(err)=>{
-                eprintln!("Error received from element {:?} {}",err.get_src().map(|s| s.get_path_string()),err.get_error());
-                eprintln!("Debugging information {:?}",err.get_debug());
+    let bus = error::bus(&pipeline)?;
+    let mut res = Ok(());
+    let mut missing = Vec::new();
+    for msg in bus.iter_timed(gst::CLOCK_TIME_NONE) {
+        match msg.view() {
+            gst::MessageView::Error(err) => {
+                res = Err(plugins::error_with_missing(
+                    Error::from_message(err),
+                    std::mem::take(&mut missing),
+                ));
                 break;
             }
-            gst::MessageView::StateChanged(state_changed)=>{ // If pipeline state was changed
-                if state_changed.get_src().map(|s| s == pipeline).unwrap_or(false){
-                    println!("Pipeline state was changed from {:?}| to {:?}",state_changed.get_old(),state_changed.get_current());
+            gst::MessageView::Element(..) => {
+                // uridecodebin can't decode a stream, e.g. the codec plugin isn't installed
+                if let Some(plugin) = MissingPlugin::from_message(&msg) {
+                    eprintln!("Missing plugin: {}", plugin);
+                    missing.push(plugin);
+                }
+            }
+            gst::MessageView::StateChanged(state_changed) => {
+                // If pipeline state was changed
+                if state_changed
+                    .get_src()
+                    .map(|s| s == pipeline)
+                    .unwrap_or(false)
+                {
+                    println!(
+                        "Pipeline state was changed from {:?}| to {:?}",
+                        state_changed.get_old(),
+                        state_changed.get_current()
+                    );
+                }
+            }
+            gst::MessageView::Buffering(buffering_msg) => {
+                let percent = buffering_msg.get_percent();
+                println!("Buffering {}%", percent);
+                if let Err(err) =
+                    buffering::handle_percent(pipeline.upcast_ref(), &mut buffering, percent)
+                {
+                    res = Err(err);
+                    break;
+                }
+            }
+            gst::MessageView::ClockLost(..) => {
+                println!("Clock lost, selecting a new one");
+                if let Err(err) = buffering::handle_clock_lost(pipeline.upcast_ref(), &buffering) {
+                    res = Err(err);
+                    break;
                 }
             }
-            gst::MessageView::Eos(..)=>break,
-            _=>()
+            gst::MessageView::Eos(..) => break,
+            _ => (),
         }
     }
 
     //Cleaning up
-    pipeline.set_state(gst::State::Null).expect("Failed to set the pipeline state to null");
-}
\ No newline at end of file
+    error::set_state(&pipeline, gst::State::Null)?;
+    res
+}
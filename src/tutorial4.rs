@@ -1,156 +1,637 @@
-extern crate gstreamer as gst;
-
+// Tutorial 4: Query position and duration, and seek in a playbin
 use gst::prelude::*;
+use std::cell::RefCell;
 use std::io;
-use std::io::Write;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use crate::cli::{self, Args};
+use crate::error::{self, Error, Result};
+use crate::media::MediaSource;
+use crate::player::{Player, PlayerEvent};
+use crate::playlist::{self, Playlist};
+use crate::plugins;
+use crate::record::{self, Finalizing, Recording};
+use crate::resume::{self, ResumeDb, ResumeState};
+use crate::seek::{self, SeekMode, Seeker};
+use crate::transcode::Profile;
+
+// Not exposed by glib, the same on every unix
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+
+// Section of the media played repeatedly, from `start` to `end`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AbLoop {
+    pub start: gst::ClockTime,
+    pub end: gst::ClockTime,
+    // How many times the section is played, None repeats it forever
+    pub repeat: Option<u32>,
+}
+
+impl Default for AbLoop {
+    // I want to play 30s - 35s
+    fn default() -> AbLoop {
+        AbLoop {
+            start: 30 * gst::SECOND,
+            end: 35 * gst::SECOND,
+            repeat: None,
+        }
+    }
+}
+
+impl AbLoop {
+    // Read --loop-start, --loop-end and --repeat. --no-loop plays the media normally.
+    pub fn from_args(args: &Args) -> Result<Option<AbLoop>> {
+        if args.has("no-loop") {
+            return Ok(None);
+        }
+        let default = AbLoop::default();
+        let ab_loop = AbLoop {
+            start: args.time("loop-start")?.unwrap_or(default.start),
+            end: args.time("loop-end")?.unwrap_or(default.end),
+            repeat: args.parse_value("repeat")?,
+        };
+        if ab_loop.end <= ab_loop.start {
+            return Err(Error::Usage(format!(
+                "Loop end {} must be after loop start {}",
+                ab_loop.end, ab_loop.start
+            )));
+        }
+        if ab_loop.repeat == Some(0) {
+            return Err(Error::Usage("--repeat must be at least 1".to_string()));
+        }
+        Ok(Some(ab_loop))
+    }
+
+    // The loop section was chosen on the command line, rather than the default one
+    pub fn is_explicit(args: &Args) -> bool {
+        ["loop-start", "loop-end", "repeat"]
+            .iter()
+            .any(|name| args.get(name).is_some())
+    }
+}
+
+// Command line options of tutorial4
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    pub ab_loop: Option<AbLoop>,
+    // --loop-start, --loop-end or --repeat was given, resuming isn't offered then
+    pub explicit_loop: bool,
+    pub seek_mode: SeekMode,
+    // Playback rate, negative to play backward
+    pub rate: f64,
+    // Resume where the media was stopped last time, None asks
+    pub resume: Option<bool>,
+    // Container and codecs of the recordings
+    pub record_profile: Profile,
+}
+
+impl Options {
+    // Switches which don't take a value
+    pub const SWITCHES: &'static [&'static str] = &["no-loop", "shuffle", "resume", "no-resume"];
+
+    pub fn from_args(args: &Args) -> Result<Options> {
+        let rate = args.parse_value("rate")?.unwrap_or(1.0f64);
+        if rate.abs() < seek::MIN_RATE || rate.abs() > seek::MAX_RATE {
+            return Err(Error::Usage(format!(
+                "--rate must be within {} - {}, or negated to play backward",
+                seek::MIN_RATE,
+                seek::MAX_RATE
+            )));
+        }
+        Ok(Options {
+            ab_loop: AbLoop::from_args(args)?,
+            explicit_loop: AbLoop::is_explicit(args),
+            seek_mode: args.parse_value("seek-mode")?.unwrap_or_default(),
+            rate,
+            resume: resume::resume_option(args),
+            record_profile: args.parse_value("record-profile")?.unwrap_or_default(),
+        })
+    }
+}
 
 // Custom data type representing application state
-struct PlayerState {
-    playbin: gst::Element,
-    playing: bool,
-    terminate: bool,
-    seek_enabled: bool,
-    first_seek_done: bool,
-    duration: gst::ClockTime,
+pub struct PlayerState {
+    pub playbin: gst::Element,
+    pub playing: bool,
+    pub terminate: bool,
+    pub seek_enabled: bool,
+    // The first seek to the loop start or applying the rate was done
+    pub first_seek_done: bool,
+    pub duration: gst::ClockTime,
+    pub ab_loop: Option<AbLoop>,
+    // Seek mode used for the loop, and playback rate
+    pub seeker: Seeker,
+    // Number of times the loop section was played until its end
+    pub loops_done: u32,
+    // Error received from the bus or failed seek which terminated the playback
+    pub error: Option<Error>,
+    // Entries played one after the other, the loop and rate apply to each of them
+    pub playlist: Option<Arc<Mutex<Playlist>>>,
+    // Last level reported while buffering a network stream, None if it was never buffered
+    pub buffering: Option<i32>,
+    // The source is live, it isn't paused for buffering and can't be seeked
+    pub live: bool,
+    // Position, tracks and volume restored by the first seek
+    pub resume_from: Option<ResumeState>,
+    // State of the entries played and their URI, captured when they are skipped or the player stops
+    pub last_states: Vec<(String, ResumeState)>,
+    // Recording in progress, started and stopped by typing r
+    pub recording: Option<Recording>,
+    pub record_profile: Profile,
+    // Recordings which were stopped, until their file is finalized
+    pub finalizing: Finalizing,
 }
 
-fn main() {
-    gst::init().unwrap();
-
-    // Create an element.
-    let playbin = gst::ElementFactory::make("playbin", Some("playbin"))
-        .expect("Failed to create playbin element");
-
-    // Set the URI to play
-    let uri =
-        "https://www.freedesktop.org/software/gstreamer-sdk/data/media/sintel_trailer-480p.webm";
-    playbin
-        .set_property("uri", &uri)
-        .expect("Can't set uri property on playbin");
-
-    // Start playing
-    playbin
-        .set_state(gst::State::Playing)
-        .expect("Unable to set the playbin to the playing state");
-
-    // Monitor messages until player_state.terminate became true
-    let bus = playbin.get_bus().unwrap();
-    let mut player_state = PlayerState {
-        playbin,
-        playing: false,
-        terminate: false,
-        seek_enabled: false,
-        first_seek_done: false,
-        duration: gst::CLOCK_TIME_NONE,
-    };
-    while !player_state.terminate {
-        let msg = bus.timed_pop(100 * gst::MSECOND);
-        match msg {
-            Some(msg) => handle_message(&mut player_state, &msg),
-            None => {
-                if player_state.playing {
-                    // Update position by query
-                    let position = player_state
-                        .playbin
-                        .query_position::<gst::ClockTime>()
-                        .expect("Could not query current position");
-                    // Query duration if player_state.duration was default value
-                    if player_state.duration == gst::CLOCK_TIME_NONE {
-                        player_state.duration = player_state
-                            .playbin
-                            .query_duration()
-                            .expect("Could not query current duration");
-                    }
-                    // Peform a first seek because it begins from 0. I want to play 30s - 35s
-                    if !player_state.first_seek_done && player_state.seek_enabled {
-                        player_state
-                            .playbin
-                            .seek_simple(
-                                gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
-                                30 * gst::SECOND,
-                            )
-                            .expect("Failed to seek");
-                        player_state.first_seek_done = true;
-                    } else {
-                        // Printing progress and duration of the video
-                        print!("\rPosition {} / {}", position, player_state.duration);
-                        io::stdout().flush().unwrap();
-
-                        // Perform a seek if the position was over 30s
-                        if player_state.seek_enabled && position > 35 * gst::SECOND {
-                            println!("\n Reached 5s performing seek...");
-                            player_state
-                                .playbin
-                                .seek_simple(
-                                    gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
-                                    30 * gst::SECOND,
-                                )
-                                .expect("Failed to seek");
-                        }
-                    }
-                }
+impl PlayerState {
+    // Player looping 30s - 35s forever
+    pub fn new(playbin: gst::Element) -> PlayerState {
+        PlayerState::with_loop(playbin, Some(AbLoop::default()))
+    }
+
+    pub fn with_loop(playbin: gst::Element, ab_loop: Option<AbLoop>) -> PlayerState {
+        PlayerState {
+            playbin,
+            playing: false,
+            terminate: false,
+            seek_enabled: false,
+            first_seek_done: false,
+            duration: gst::CLOCK_TIME_NONE,
+            ab_loop,
+            seeker: Seeker::default(),
+            loops_done: 0,
+            error: None,
+            playlist: None,
+            buffering: None,
+            live: false,
+            resume_from: None,
+            last_states: Vec::new(),
+            recording: None,
+            record_profile: Profile::default(),
+            finalizing: Finalizing::default(),
+        }
+    }
+}
+
+pub fn run(media: &MediaSource, options: &Options) -> Result<()> {
+    play(media, options, None)
+}
+
+// Play every entry of the playlist without gap. Typing n or p then Enter skips to the next
+// or previous entry.
+pub fn run_playlist(playlist: Playlist, options: &Options) -> Result<()> {
+    let media = MediaSource::Uri(playlist.current().to_string());
+    play(&media, options, Some(Arc::new(Mutex::new(playlist))))
+}
+
+fn play(
+    media: &MediaSource,
+    options: &Options,
+    playlist: Option<Arc<Mutex<Playlist>>>,
+) -> Result<()> {
+    error::init()?;
+    plugins::check(media.player_elements())?;
+
+    // Create an element playing the media given on the command line.
+    let playbin = media.make_player(Some("playbin"))?;
+    if media.uri().is_some() && !seek::use_scaletempo(&playbin)? {
+        println!("scaletempo is not installed, audio pitch changes with the rate");
+    }
+
+    if let Some(playlist) = &playlist {
+        playlist::connect_gapless(&playbin, playlist.clone())?;
+    }
+    // The sinks can only be replaced before playing
+    let recordable = record::use_tee_sinks(&playbin)?;
+
+    let mut player_state = PlayerState::with_loop(playbin.clone(), options.ab_loop);
+    player_state.playlist = playlist;
+    player_state.seeker.mode = options.seek_mode;
+    player_state.seeker.rate = options.rate;
+    player_state.record_profile = options.record_profile;
+
+    // Resuming is offered unless a loop was asked for, the default loop is dropped when resumed
+    let mut db = ResumeDb::load();
+    if let (Some(uri), false) = (media.uri(), options.explicit_loop) {
+        if let Some(state) = db.resumable(uri) {
+            if options.resume.unwrap_or_else(|| ask_resume(state)) {
+                player_state.ab_loop = None;
+                player_state.seeker.rate = state.rate;
+                player_state.resume_from = Some(state.clone());
             }
         }
     }
 
-    // Cleaning up
+    // Read after the question above, which reads stdin too
+    if player_state.playlist.is_some() || recordable {
+        read_commands(&playbin);
+    }
+    if recordable {
+        println!("Type r then Enter to start or stop recording");
+    }
+
+    let mut player_state = play_until(player_state, |_| false)?;
+    if !player_state.last_states.is_empty() {
+        for (uri, state) in player_state.last_states.drain(..) {
+            db.record(&uri, state);
+        }
+        if let Err(err) = db.save() {
+            eprintln!("{}", err);
+        }
+    }
+    match player_state.error.take() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+// Ask on the terminal, the media starts over unless the answer is yes
+fn ask_resume(state: &ResumeState) -> bool {
+    print!("Resume from {}? [y/N] ", cli::format_time(state.position()));
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+}
+
+// Play on a GLib main loop until EOS, an error or `done` returns true after an event
+pub fn play_until<F>(player_state: PlayerState, done: F) -> Result<PlayerState>
+where
+    F: Fn(&PlayerState) -> bool + 'static,
+{
+    // Own main context, so that the events are dispatched on this thread
+    let context = glib::MainContext::new();
+    context.push_thread_default();
+    let main_loop = glib::MainLoop::new(Some(&context), false);
+
+    let playbin = player_state.playbin.clone();
+    let player_state = Rc::new(RefCell::new(player_state));
+    let handler_state = player_state.clone();
+    let handler_loop = main_loop.clone();
+    // Ctrl+C ends the playback like EOS, so that it is cleaned up and its state recorded
+    #[cfg(unix)]
+    let interrupt = {
+        let interrupt_loop = main_loop.clone();
+        let interrupt =
+            glib::unix_signal_source_new(SIGINT, None, glib::PRIORITY_DEFAULT, move || {
+                interrupt_loop.quit();
+                glib::Continue(false)
+            });
+        interrupt.attach(Some(&context));
+        interrupt
+    };
+
+    let player = Player::new(playbin, move |event| {
+        let mut player_state = handler_state.borrow_mut();
+        if let Err(err) = handle_event(&mut player_state, event) {
+            player_state.error = Some(err);
+            player_state.terminate = true;
+        }
+        if player_state.terminate || done(&player_state) {
+            handler_loop.quit();
+        }
+    });
+    let player = match player {
+        Ok(player) => player,
+        Err(err) => {
+            #[cfg(unix)]
+            interrupt.destroy();
+            context.pop_thread_default();
+            return Err(err);
+        }
+    };
+
+    // Start playing, then monitor events until player_state.terminate became true
+    let res = player.play();
+    if res.is_ok() {
+        if player.is_live() {
+            println!("Live source, playing without buffering");
+            player_state.borrow_mut().live = true;
+        }
+        main_loop.run();
+    }
+
+    // Cleaning up after EOS, an error or Ctrl+C, the state is captured and the recordings are
+    // finalized while the pipeline still runs
+    {
+        let mut player_state = player_state.borrow_mut();
+        capture_state(&mut player_state);
+        if let Some(recording) = player_state.recording.take() {
+            player_state.finalizing.push(recording.stop());
+        }
+        report_recordings(player_state.finalizing.finish_all());
+    }
+    #[cfg(unix)]
+    interrupt.destroy();
+    let res = res.and(player.stop());
+    drop(player);
+    context.pop_thread_default();
+    res?;
+    Rc::try_unwrap(player_state)
+        .map(RefCell::into_inner)
+        .map_err(|_| Error::StillShared("player state"))
+}
+
+// Post the commands typed on stdin as application messages, so that they are handled on the main loop
+fn read_commands(playbin: &gst::Element) {
+    let playbin = playbin.clone();
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let name = match line {
+                Ok(ref line) if line.trim() == "n" => playlist::NEXT_MESSAGE,
+                Ok(ref line) if line.trim() == "p" => playlist::PREVIOUS_MESSAGE,
+                Ok(ref line) if line.trim() == "r" => record::RECORD_MESSAGE,
+                Ok(_) => continue,
+                Err(_) => break,
+            };
+            let _ = playbin.post_message(
+                &gst::Message::new_application(gst::Structure::new_empty(name)).build(),
+            );
+        }
+    });
+}
+
+// Skip to the next or previous entry of the playlist
+fn handle_playlist_command(player_state: &mut PlayerState, name: &str) -> Result<()> {
+    let uri = match &player_state.playlist {
+        Some(playlist) => {
+            let mut playlist = playlist.lock().unwrap();
+            let uri = match name {
+                playlist::NEXT_MESSAGE => playlist.next(),
+                playlist::PREVIOUS_MESSAGE => playlist.previous(),
+                _ => return Ok(()),
+            };
+            uri.map(|uri| uri.to_string())
+        }
+        None => return Ok(()),
+    };
+    match uri {
+        Some(uri) => {
+            capture_state(player_state);
+            playlist::play_entry(&player_state.playbin, &uri)
+        }
+        None => {
+            println!("\nNo more entries in the playlist");
+            Ok(())
+        }
+    }
+}
+
+// Start recording what is played, or stop it, the file is finalized in the background.
+// Failures don't stop the playback.
+fn toggle_recording(player_state: &mut PlayerState) {
+    if let Some(recording) = player_state.recording.take() {
+        println!("\nFinalizing {}", recording.location().display());
+        player_state.finalizing.push(recording.stop());
+        // Nothing flows after EOS, the file may be complete already
+        report_recordings(player_state.finalizing.finish_done());
+        return;
+    }
+    let location = record::default_location(player_state.record_profile);
+    match Recording::start(
+        &player_state.playbin,
+        &location,
+        player_state.record_profile,
+    ) {
+        Ok(recording) => {
+            println!("\nRecording to {}", location.display());
+            player_state.recording = Some(recording);
+        }
+        Err(err) => eprintln!("\n{}", err),
+    }
+}
+
+// Keep the state of the entry being played, to resume it on the next run
+fn capture_state(player_state: &mut PlayerState) {
+    let uri = resume::current_uri(&player_state.playbin);
+    let state = ResumeState::capture(&player_state.playbin, player_state.seeker.rate);
+    if let (Some(uri), Some(state)) = (uri, state) {
+        player_state.last_states.push((uri, state));
+    }
+}
+
+fn report_recordings(finished: Vec<Result<PathBuf>>) {
+    for res in finished {
+        match res {
+            Ok(location) => println!("\nRecorded {}", location.display()),
+            Err(err) => eprintln!("\n{}", err),
+        }
+    }
+}
+
+// A playlist entry started, the loop and rate are applied to it again
+fn handle_stream_start(player_state: &mut PlayerState) -> Result<()> {
+    let playlist = match &player_state.playlist {
+        Some(playlist) => playlist.lock().unwrap(),
+        None => return Ok(()),
+    };
+    println!(
+        "\nPlaying {}/{}: {}",
+        playlist.current_index() + 1,
+        playlist.len(),
+        playlist.current()
+    );
+    drop(playlist);
+    player_state.first_seek_done = false;
+    player_state.loops_done = 0;
+    // Skipping restarts playbin, the first seek is done once playing again.
+    // Gapless transitions keep playing.
+    if player_state.playing {
+        first_seek(player_state)?;
+    }
+    Ok(())
+}
+
+// Print the progress of the playback
+fn handle_position(
+    player_state: &mut PlayerState,
+    position: gst::ClockTime,
+    duration: gst::ClockTime,
+) {
+    if !player_state.playing {
+        return;
+    }
+    player_state.duration = duration;
+
+    // Printing progress and duration of the video
+    print!(
+        "\rPosition {} / {} (rate {:.2}x)",
+        position, player_state.duration, player_state.seeker.rate
+    );
+    let _ = io::stdout().flush();
+}
+
+// Play the loop section with a segment seek. When it reaches the end, the pipeline posts SegmentDone
+// instead of EOS, so the next repetition can be queued without flushing for a gapless loop.
+fn seek_loop(player_state: &PlayerState, ab_loop: &AbLoop, flush: bool) -> Result<()> {
+    let mut flags = gst::SeekFlags::SEGMENT | player_state.seeker.mode.flags();
+    if flush {
+        flags |= gst::SeekFlags::FLUSH;
+    }
     player_state
         .playbin
-        .set_state(gst::State::Null)
-        .expect("Unable to set playbin to the Null state");
+        .seek(
+            player_state.seeker.rate,
+            flags,
+            gst::SeekType::Set,
+            ab_loop.start,
+            gst::SeekType::Set,
+            ab_loop.end,
+        )
+        .map_err(|_| Error::Seek(format!("to loop {} - {}", ab_loop.start, ab_loop.end)))
 }
 
-fn handle_message(player_state: &mut PlayerState, msg: &gst::Message) {
-    match msg.view() {
-        gst::MessageView::Error(err) => {
+// Leave the loop by continuing the playback from its end up to the end of the media,
+// or from its start down to the beginning when playing backward
+fn seek_after_loop(player_state: &PlayerState, ab_loop: &AbLoop) -> Result<()> {
+    let rate = player_state.seeker.rate;
+    let (start, stop_type, stop) = if rate > 0.0 {
+        (ab_loop.end, gst::SeekType::None, gst::CLOCK_TIME_NONE)
+    } else {
+        (
+            gst::ClockTime::from_seconds(0),
+            gst::SeekType::Set,
+            ab_loop.start,
+        )
+    };
+    player_state
+        .playbin
+        .seek(
+            rate,
+            player_state.seeker.mode.flags(),
+            gst::SeekType::Set,
+            start,
+            stop_type,
+            stop,
+        )
+        .map_err(|_| Error::Seek(format!("after loop {} - {}", ab_loop.start, ab_loop.end)))
+}
+
+// Peform a first seek because it begins from 0 at normal rate
+fn first_seek(player_state: &mut PlayerState) -> Result<()> {
+    if !player_state.seek_enabled || player_state.first_seek_done {
+        return Ok(());
+    }
+    if let Some(ab_loop) = player_state.ab_loop {
+        seek_loop(player_state, &ab_loop, true)?;
+    } else if let Some(resume) = player_state.resume_from.take() {
+        println!("Resuming from {}", cli::format_time(resume.position()));
+        resume.restore_tracks(&player_state.playbin)?;
+        player_state
+            .seeker
+            .seek(&player_state.playbin, resume.position())?;
+    } else if (player_state.seeker.rate - 1.0).abs() > f64::EPSILON {
+        // Playing backward starts from the end
+        let position = if player_state.seeker.rate > 0.0 {
+            player_state.playbin.query_position::<gst::ClockTime>()
+        } else {
+            player_state.playbin.query_duration::<gst::ClockTime>()
+        };
+        let position = position.ok_or(Error::Query("position"))?;
+        player_state.seeker.seek(&player_state.playbin, position)?;
+    }
+    player_state.first_seek_done = true;
+    Ok(())
+}
+
+// The loop section was played until its end, repeat it or continue after it
+fn handle_segment_done(player_state: &mut PlayerState) -> Result<()> {
+    let ab_loop = match player_state.ab_loop {
+        Some(ab_loop) => ab_loop,
+        None => return Ok(()),
+    };
+    player_state.loops_done += 1;
+    match ab_loop.repeat {
+        Some(repeat) if player_state.loops_done >= repeat => {
             println!(
-                "Error received from element {:?}: {} ({:?})",
-                err.get_src().map(|s| s.get_path_string()),
-                err.get_error(),
-                err.get_debug()
+                "\n Played {} - {} {} times",
+                ab_loop.start, ab_loop.end, repeat
             );
+            seek_after_loop(player_state, &ab_loop)
+        }
+        _ => {
+            println!(
+                "\n Reached {} looping back to {}...",
+                ab_loop.end, ab_loop.start
+            );
+            seek_loop(player_state, &ab_loop, false)
+        }
+    }
+}
+
+pub fn handle_event(player_state: &mut PlayerState, event: PlayerEvent) -> Result<()> {
+    match event {
+        PlayerEvent::Error(err) => {
+            player_state.error = Some(err);
             player_state.terminate = true;
         }
-        gst::MessageView::Eos(..) => {
+        PlayerEvent::MissingPlugin(plugin) => {
+            // playbin can't play a stream, e.g. the codec plugin isn't installed
+            println!("\nMissing plugin: {}", plugin);
+        }
+        PlayerEvent::Eos => {
             println!("EOS");
             player_state.terminate = true;
         }
-        gst::MessageView::DurationChanged(_) => {
-            player_state.duration = gst::CLOCK_TIME_NONE;
-        }
-        gst::MessageView::StateChanged(state_changed) => {
-            if state_changed
-                .get_src()
-                .map(|s| s == player_state.playbin)
-                .unwrap_or(false)
-            {
-                let new_state = state_changed.get_current();
-                let old_state = state_changed.get_old();
-
-                println!(
-                    "Pipeline state changed from {:?} to {:?}",
-                    old_state, new_state
-                );
-
-                // If player state became first, we need to check that stream being seekable
-                // Some streams can be unseekale.
-                player_state.playing = new_state == gst::State::Playing;
-                if player_state.playing {
-                    let mut seeking = gst::Query::new_seeking(gst::Format::Time);
-                    if player_state.playbin.query(&mut seeking) {
-                        let (seekable, start, end) = seeking.get_result();
-                        player_state.seek_enabled = seekable;
-                        if seekable {
-                            println!("Seeking is ENABLED from {:?} to {:?}", start, end)
-                        } else {
-                            println!("Seeking is DISABLED for this stream.")
-                        }
+        PlayerEvent::SegmentDone => {
+            handle_segment_done(player_state)?;
+        }
+        PlayerEvent::Buffering(percent) => {
+            // The player pauses the pipeline until the buffer is full
+            print!("\rBuffering {}%  ", percent);
+            if percent == 100 {
+                println!();
+            }
+            let _ = io::stdout().flush();
+            player_state.buffering = Some(percent);
+        }
+        PlayerEvent::ClockLost => {
+            println!("\nClock lost, selecting a new one");
+        }
+        PlayerEvent::StreamStart => {
+            handle_stream_start(player_state)?;
+        }
+        PlayerEvent::Application(name) => {
+            if name == record::RECORD_MESSAGE {
+                toggle_recording(player_state);
+            } else if name == record::FINALIZED_MESSAGE {
+                report_recordings(player_state.finalizing.finish_done());
+            } else {
+                handle_playlist_command(player_state, &name)?;
+            }
+        }
+        PlayerEvent::Position { position, duration } => {
+            // Gives up on the recordings which didn't finalize in time
+            if !player_state.finalizing.is_empty() {
+                report_recordings(player_state.finalizing.finish_done());
+            }
+            handle_position(player_state, position, duration);
+        }
+        PlayerEvent::StateChanged { old, current } => {
+            println!("Pipeline state changed from {:?} to {:?}", old, current);
+
+            // If player state became first, we need to check that stream being seekable
+            // Some streams can be unseekale.
+            player_state.playing = current == gst::State::Playing;
+            if player_state.playing {
+                let mut seeking = gst::Query::new_seeking(gst::Format::Time);
+                if player_state.playbin.query(&mut seeking) {
+                    let (seekable, start, end) = seeking.get_result();
+                    player_state.seek_enabled = seekable;
+                    if seekable {
+                        println!("Seeking is ENABLED from {:?} to {:?}", start, end)
                     } else {
-                        eprintln!("Seeking query failed.")
+                        println!("Seeking is DISABLED for this stream.")
                     }
+                } else {
+                    eprintln!("Seeking query failed.")
                 }
+                first_seek(player_state)?;
             }
         }
         _ => (),
     }
+    Ok(())
 }
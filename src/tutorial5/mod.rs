@@ -0,0 +1,866 @@
+// Tutorial 5: GTK player embedding the playbin video on X11, Wayland or other GDK backends,
+// with seek bar and stream information.
+// This module only exists if --features tutorial5 was specified on build,
+// gdk and gtk won't be available if there are no #[cfg(feature = "tutorial5")] on scope they enclosed in.
+use gst::prelude::*;
+use gtk::*;
+
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use crate::buffering::{self, Buffering};
+use crate::cli::{self, Args};
+use crate::error::{self, Error, Result};
+use crate::media::MediaSource;
+use crate::player::{Player, PlayerEvent};
+use crate::playlist::{self, Playlist, RepeatMode};
+use crate::plugins;
+use crate::record::{self, Finalizing, Recording};
+use crate::resume::{self, ResumeDb, ResumeState};
+use crate::seek::{self, SeekMode, Seeker};
+use crate::settings::Settings;
+use crate::snapshot::{self, Size};
+use crate::subtitles;
+use crate::transcode::Profile;
+
+mod panel;
+mod seekbar;
+mod shortcuts;
+mod video;
+mod volume;
+
+pub use self::panel::StreamsPanel;
+pub use self::seekbar::SeekBar;
+pub use self::shortcuts::{Fullscreen, Shortcut};
+pub use self::video::{FrameRenderer, Letterbox, Renderer};
+pub use self::volume::AudioControls;
+
+// Command line options of tutorial5
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Options {
+    pub renderer: Renderer,
+    // External subtitle file, path or URI
+    pub subtitles: Option<String>,
+    // Pango font description of the subtitles, e.g. "Sans Bold 24"
+    pub subtitle_font: Option<String>,
+    pub no_subtitles: bool,
+    // Initial mode of the seek bar, changed with the seek mode selector
+    pub seek_mode: SeekMode,
+    // Resume where the media was stopped last time, None asks
+    pub resume: Option<bool>,
+    // Container and codecs of the recordings
+    pub record_profile: Profile,
+}
+
+impl Options {
+    // Switches which don't take a value
+    pub const SWITCHES: &'static [&'static str] =
+        &["no-subtitles", "shuffle", "resume", "no-resume"];
+
+    pub fn from_args(args: &Args) -> Result<Options> {
+        let subtitles = args.get("subtitles").map(|s| s.to_string());
+        if let Some(file) = &subtitles {
+            if !gst::uri_is_valid(file) && !subtitles::is_subtitle_file(file) {
+                return Err(Error::Usage(format!(
+                    "{} is not a subtitle file ({})",
+                    file,
+                    subtitles::EXTENSIONS.join(", ")
+                )));
+            }
+        }
+        Ok(Options {
+            renderer: args.parse_value("renderer")?.unwrap_or_default(),
+            subtitles,
+            subtitle_font: args.get("subtitle-font").map(|s| s.to_string()),
+            no_subtitles: args.has("no-subtitles"),
+            seek_mode: args.parse_value("seek-mode")?.unwrap_or_default(),
+            resume: resume::resume_option(args),
+            record_profile: args.parse_value("record-profile")?.unwrap_or_default(),
+        })
+    }
+}
+
+pub fn run(media: &MediaSource, options: &Options) -> Result<()> {
+    play(media, options, None)
+}
+
+// Play every entry of the playlist without gap, with previous/next buttons
+pub fn run_playlist(playlist: Playlist, options: &Options) -> Result<()> {
+    let media = MediaSource::Uri(playlist.current().to_string());
+    play(&media, options, Some(Arc::new(Mutex::new(playlist))))
+}
+
+fn play(
+    media: &MediaSource,
+    options: &Options,
+    playlist: Option<Arc<Mutex<Playlist>>>,
+) -> Result<()> {
+    initialize_gtk_gstreaner()?; // Initialize gtk and gstreamer
+    plugins::check(media.player_elements())?;
+
+    // Volume, mute and audio sink of the previous run
+    let settings = Rc::new(RefCell::new(Settings::load()));
+
+    // Initialize playbin with the media given on the command line
+    let playbin = media.make_player(None)?;
+
+    // Tags are only reported by playbin, synthetic sources don't have them
+    if media.uri().is_some() {
+        connect_tags_changed(&playbin)?;
+        // Keep the audio pitch when changing the playback rate
+        if !seek::use_scaletempo(&playbin)? {
+            println!("scaletempo is not installed, audio pitch changes with the rate");
+        }
+
+        if let Some(file) = &options.subtitles {
+            subtitles::set_external(&playbin, file)?;
+        }
+        if let Some(font_desc) = &options.subtitle_font {
+            subtitles::set_font(&playbin, font_desc)?;
+        }
+        if options.no_subtitles {
+            subtitles::set_enabled(&playbin, false)?;
+        }
+
+        // A sink which was uninstalled since is not fatal
+        if let Err(err) = volume::apply_settings(&playbin, &settings.borrow()) {
+            eprintln!("{}", err);
+        }
+    }
+
+    if let Some(playlist) = &playlist {
+        playlist::connect_gapless(&playbin, playlist.clone())?;
+    }
+
+    // Position, tracks and volume where the media was stopped last time
+    let resume_from = media
+        .uri()
+        .and_then(|uri| ResumeDb::load().resumable(uri).cloned())
+        .filter(|state| options.resume.unwrap_or_else(|| ask_resume(state)));
+
+    // Recordings stopped from the ui, finished when their file is finalized
+    let finalizing = Rc::new(RefCell::new(Finalizing::default()));
+
+    // Construct the ui, which follows the playback from the player events
+    let player = create_ui(
+        &playbin,
+        options,
+        settings.clone(),
+        playlist,
+        resume_from,
+        finalizing.clone(),
+    )?;
+
+    // start [;auomg]
+    player.play()?;
+    // Start the GTK main loop. We will not regain control until gtk::main_quit(); is called.
+    gtk::main();
+
+    // Cleaning up, the window is gone while the recordings are finalized
+    report_recordings(finalizing.borrow_mut().finish_all());
+    player.stop()?;
+    let settings = settings.borrow();
+    settings.save()
+}
+
+// Be notified when the tags of a video, audio or text stream changed
+fn connect_tags_changed(playbin: &gst::Element) -> Result<()> {
+    for &signal in &[
+        "video-tags-changed",
+        "audio-tags-changed",
+        "text-tags-changed",
+    ] {
+        playbin
+            .connect(signal, false, |args| {
+                if let Some(pipeline) = args[0].get::<gst::Element>().ok().flatten() {
+                    // This will send message to application thread
+                    post_app_message(&pipeline);
+                }
+                None
+            })
+            .map_err(|_| Error::Property {
+                element: playbin.get_name().to_string(),
+                property: signal.to_string(),
+            })?;
+    }
+    Ok(())
+}
+
+fn initialize_gtk_gstreaner() -> Result<()> {
+    gtk::init().map_err(|err| Error::Gtk(err.to_string()))?;
+    error::init()
+}
+
+fn create_ui(
+    playbin: &gst::Element,
+    options: &Options,
+    settings: Rc<RefCell<Settings>>,
+    playlist: Option<Arc<Mutex<Playlist>>>,
+    resume_from: Option<ResumeState>,
+    finalizing: Rc<RefCell<Finalizing>>,
+) -> Result<Player> {
+    // Instanciate window, button, seek bar and register their event handlers
+    let main_window = Window::new(WindowType::Toplevel);
+    main_window.connect_delete_event(|_, _| {
+        gtk::main_quit();
+        Inhibit(false)
+    });
+
+    // State asked by the user, network streams stay paused until their buffer is full
+    let buffering = Rc::new(RefCell::new(Buffering::default()));
+
+    let pipeline = playbin.clone();
+    let play_buffering = buffering.clone();
+    let play_button =
+        gtk::Button::new_from_icon_name(Some("media-playback-start"), gtk::IconSize::SmallToolbar);
+    play_button.connect_clicked(move |_| {
+        // Add event handler to the event  when the button was clicked
+        let mut buffering = play_buffering.borrow_mut();
+        if let Err(err) = buffering::request_state(&pipeline, &mut buffering, gst::State::Playing) {
+            eprintln!("{}", err);
+        }
+    });
+
+    let pause_button =
+        gtk::Button::new_from_icon_name(Some("media-playback-pause"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let pause_buffering = buffering.clone();
+    pause_button.connect_clicked(move |_| {
+        // Add event handler to the event  when the button was clicked
+        let mut buffering = pause_buffering.borrow_mut();
+        if let Err(err) = buffering::request_state(&pipeline, &mut buffering, gst::State::Paused) {
+            eprintln!("{}", err);
+        }
+    });
+
+    // Seek mode and playback direction shared by the stop button, the seek bar and the step buttons
+    let seeker = Rc::new(RefCell::new(Seeker::new(options.seek_mode)));
+
+    // Remember where the media was stopped, to resume it next time
+    let pipeline = playbin.clone();
+    let quit_seeker = seeker.clone();
+    main_window.connect_delete_event(move |_, _| {
+        if let Err(err) = resume::remember(&pipeline, quit_seeker.borrow().rate) {
+            eprintln!("{}", err);
+        }
+        Inhibit(false)
+    });
+
+    let stop_button =
+        gtk::Button::new_from_icon_name(Some("media-playback_stop"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let stop_seeker = seeker.clone();
+    let stop_buffering = buffering.clone();
+    stop_button.connect_clicked(move |_| {
+        // Add event handler to the event when the button was clicked
+        let pipeline = &pipeline;
+        if let Err(err) = stop_seeker.borrow().seek(pipeline, 0 * gst::MSECOND) {
+            eprintln!("{}", err);
+        }
+        let mut buffering = stop_buffering.borrow_mut();
+        if let Err(err) = buffering::request_state(pipeline, &mut buffering, gst::State::Paused) {
+            eprintln!("{}", err);
+        }
+    });
+
+    // Step one frame backward or forward, the pipeline is paused while stepping
+    let step_back_button =
+        gtk::Button::new_from_icon_name(Some("go-previous"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let step_seeker = seeker.clone();
+    step_back_button.connect_clicked(move |_| {
+        if let Err(err) = step_seeker.borrow_mut().step(&pipeline, -1) {
+            eprintln!("{}", err);
+        }
+    });
+
+    let step_forward_button =
+        gtk::Button::new_from_icon_name(Some("go-next"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let step_seeker = seeker.clone();
+    step_forward_button.connect_clicked(move |_| {
+        if let Err(err) = step_seeker.borrow_mut().step(&pipeline, 1) {
+            eprintln!("{}", err);
+        }
+    });
+
+    // Select how the seek bar lands when released or moved from the keyboard
+    let seek_mode_combo = gtk::ComboBoxText::new();
+    for mode in SeekMode::ALL.iter() {
+        seek_mode_combo.append(Some(mode.name()), mode.name());
+    }
+    seek_mode_combo.set_active_id(Some(seeker.borrow().mode.name()));
+    let mode_seeker = seeker.clone();
+    seek_mode_combo.connect_changed(move |combo| {
+        if let Some(mode) = combo.get_active_id().and_then(|id| id.parse().ok()) {
+            mode_seeker.borrow_mut().mode = mode;
+        }
+    });
+
+    // Playback rate, the label shows the current rate and direction
+    let rate_label = gtk::Label::new(Some(&format_rate(seeker.borrow().rate)));
+    let slower_button =
+        gtk::Button::new_from_icon_name(Some("media-seek-backward"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let rate_seeker = seeker.clone();
+    let label = rate_label.clone();
+    slower_button.connect_clicked(move |_| {
+        if let Err(err) = rate_seeker.borrow_mut().slower(&pipeline) {
+            eprintln!("{}", err);
+        }
+        label.set_text(&format_rate(rate_seeker.borrow().rate));
+    });
+
+    let faster_button =
+        gtk::Button::new_from_icon_name(Some("media-seek-forward"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let rate_seeker = seeker.clone();
+    let label = rate_label.clone();
+    faster_button.connect_clicked(move |_| {
+        if let Err(err) = rate_seeker.borrow_mut().faster(&pipeline) {
+            eprintln!("{}", err);
+        }
+        label.set_text(&format_rate(rate_seeker.borrow().rate));
+    });
+
+    let reverse_button = gtk::Button::new_from_icon_name(
+        Some("object-flip-horizontal"),
+        gtk::IconSize::SmallToolbar,
+    );
+    let pipeline = playbin.clone();
+    let rate_seeker = seeker.clone();
+    let label = rate_label.clone();
+    reverse_button.connect_clicked(move |_| {
+        if let Err(err) = rate_seeker.borrow_mut().reverse(&pipeline) {
+            eprintln!("{}", err);
+        }
+        label.set_text(&format_rate(rate_seeker.borrow().rate));
+    });
+
+    // Seek bar with the elapsed and remaining time
+    let seek_bar = SeekBar::new(playbin, seeker.clone());
+    // Only shown while a network stream is buffering
+    let buffering_label = gtk::Label::new(None);
+    buffering_label.set_no_show_all(true);
+
+    // Pack UI in tool bar
+    let controls = Box::new(Orientation::Horizontal, 0);
+    controls.pack_start(&play_button, false, false, 0);
+    controls.pack_start(&pause_button, false, false, 0);
+    controls.pack_start(&stop_button, false, false, 0);
+    controls.pack_start(&step_back_button, false, false, 0);
+    controls.pack_start(&step_forward_button, false, false, 0);
+    controls.pack_start(&slower_button, false, false, 0);
+    controls.pack_start(&rate_label, false, false, 2);
+    controls.pack_start(&faster_button, false, false, 0);
+    controls.pack_start(&reverse_button, false, false, 0);
+    controls.pack_start(seek_bar.widget(), true, true, 2);
+    controls.pack_start(&buffering_label, false, false, 2);
+    controls.pack_start(&seek_mode_combo, false, false, 2);
+    if let Some(playlist) = &playlist {
+        let playlist_controls = create_playlist_controls(playbin, playlist.clone());
+        controls.pack_start(&playlist_controls, false, false, 2);
+        main_window.set_title(&playlist_title(&playlist.lock().unwrap()));
+    }
+
+    // Position to seek back to after playbin was reconfigured, with new subtitles or audio sink
+    let reconfigure_seek = Rc::new(Cell::new(None));
+    // Only playbin has subtitles, synthetic sources are played by a plain pipeline
+    if playbin.find_property("suburi").is_some() {
+        let subtitle_controls =
+            create_subtitle_controls(&main_window, playbin, reconfigure_seek.clone());
+        controls.pack_start(&subtitle_controls, false, false, 2);
+    }
+
+    // Synthetic sources have no volume either
+    let audio_controls = if playbin.find_property("volume").is_some() {
+        let audio_controls = AudioControls::new(playbin, settings, reconfigure_seek.clone());
+        controls.pack_start(audio_controls.widget(), false, false, 2);
+        Some(audio_controls)
+    } else {
+        None
+    };
+    let audio_controls = Rc::new(audio_controls);
+
+    // Create video area, depending on the display GDK is running on
+    let video_output = video::create(playbin, options.renderer);
+    if let Some(frame_renderer) = &video_output.frame_renderer {
+        // The frames are drawn by us, show the rate over them when it isn't 1x
+        let overlay_seeker = seeker.clone();
+        frame_renderer.add_overlay(move |cr, letterbox| {
+            let rate = overlay_seeker.borrow().rate;
+            if (rate - 1.0).abs() > f64::EPSILON {
+                draw_rate(cr, letterbox, rate);
+            }
+        });
+    }
+    let video_window = video_output.widget;
+
+    // Save the frame being shown, only playbin keeps it
+    if playbin.find_property("sample").is_some() {
+        let snapshot_button =
+            gtk::Button::new_from_icon_name(Some("camera-photo"), gtk::IconSize::SmallToolbar);
+        snapshot_button.set_tooltip_text(Some("Snapshot"));
+        let pipeline = playbin.clone();
+        snapshot_button.connect_clicked(move |_| {
+            let location = snapshot::default_location();
+            match snapshot::save(&pipeline, &location, Size::default()) {
+                Ok(()) => println!("Saved snapshot {}", location.display()),
+                Err(err) => eprintln!("{}", err),
+            }
+        });
+        controls.pack_start(&snapshot_button, false, false, 2);
+    }
+
+    // Recording needs a tee in front of the sinks, which are all chosen now
+    let record_button = match record::use_tee_sinks(playbin) {
+        Ok(true) => {
+            let record_button = create_record_button(
+                &main_window,
+                playbin,
+                options.record_profile,
+                finalizing.clone(),
+            );
+            controls.pack_start(&record_button, false, false, 2);
+            Some(record_button)
+        }
+        Ok(false) => None,
+        Err(err) => {
+            eprintln!("{}", err);
+            None
+        }
+    };
+
+    // Initialize stream panel which shows the streams available in the media file
+    let streams_panel = StreamsPanel::new(playbin);
+
+    // Pack video region and stream info side bar
+    let vbox = Box::new(Orientation::Horizontal, 0);
+    vbox.pack_start(&video_window, true, true, 0);
+    vbox.pack_start(streams_panel.widget(), false, false, 2);
+
+    let main_box = Box::new(Orientation::Vertical, 0);
+    main_box.pack_start(&controls, false, false, 0);
+    main_box.pack_start(&vbox, true, true, 0);
+    main_window.add(&main_box);
+    main_window.set_default_size(640, 480);
+    main_window.show_all();
+
+    // Double-click on the video or F for fullscreen, where the control bar hides by itself
+    let fullscreen = Fullscreen::new(
+        &main_window,
+        controls.upcast_ref(),
+        streams_panel.widget().upcast_ref(),
+    );
+    video_window.add_events(gdk::EventMask::BUTTON_PRESS_MASK);
+    let video_fullscreen = fullscreen.clone();
+    video_window.connect_button_press_event(move |_, event| {
+        if event.get_event_type() == gdk::EventType::DoubleButtonPress {
+            video_fullscreen.toggle();
+        }
+        Inhibit(false)
+    });
+
+    // Shortcuts take precedence over the focused widget, e.g. space doesn't click a button
+    let pipeline = playbin.clone();
+    let key_seeker = seeker.clone();
+    let key_buffering = buffering.clone();
+    let key_audio_controls = audio_controls.clone();
+    main_window.connect_key_press_event(move |_, event| {
+        let shortcut = match Shortcut::from_key(event.get_keyval(), event.get_state()) {
+            Some(shortcut) => shortcut,
+            None => return Inhibit(false),
+        };
+        let res = match shortcut {
+            Shortcut::PlayPause => toggle_pause(&pipeline, &mut key_buffering.borrow_mut()),
+            Shortcut::Seek(seconds) => key_seeker.borrow().seek_by(&pipeline, seconds),
+            Shortcut::Volume(delta) => {
+                if let Some(audio_controls) = &*key_audio_controls {
+                    audio_controls.change_volume(delta);
+                }
+                Ok(())
+            }
+            Shortcut::Step(frames) => key_seeker.borrow_mut().step(&pipeline, frames),
+            Shortcut::ToggleFullscreen => {
+                fullscreen.toggle();
+                Ok(())
+            }
+            Shortcut::LeaveFullscreen => {
+                fullscreen.leave();
+                Ok(())
+            }
+        };
+        if let Err(err) = res {
+            eprintln!("{}", err);
+        }
+        Inhibit(true)
+    });
+
+    // Update the seek bar from the position reports and handle the bus messages
+    let pipeline = playbin.clone();
+    let player_buffering = buffering.clone();
+    let resume_from = RefCell::new(resume_from);
+    Player::with_buffering(playbin.clone(), buffering, move |event| match event {
+        PlayerEvent::Position { position, duration } => {
+            // Gives up on the recordings which didn't finalize in time
+            if !finalizing.borrow().is_empty() {
+                report_recordings(finalizing.borrow_mut().finish_done());
+            }
+            seek_bar.update(position, duration);
+            // Stepping backward reverses the direction too
+            rate_label.set_text(&format_rate(seeker.borrow().rate));
+        }
+        PlayerEvent::Volume { volume, muted } => {
+            if let Some(audio_controls) = &*audio_controls {
+                audio_controls.update(volume, muted);
+            }
+        }
+        // application message is the message engineer can control
+        // You can send arbitary message, you can see some messages are sent from post_app_message
+        PlayerEvent::Application(name) => {
+            if name == "tags-changed" {
+                streams_panel.refresh();
+            } else if name == record::FINALIZED_MESSAGE {
+                report_recordings(finalizing.borrow_mut().finish_done());
+            }
+        }
+        // The player already paused the pipeline until the buffer is full
+        PlayerEvent::Buffering(percent) => {
+            if percent < 100 {
+                buffering_label.set_text(&format!("Buffering {}%", percent));
+                buffering_label.show();
+            } else {
+                buffering_label.hide();
+            }
+        }
+        PlayerEvent::ClockLost => println!("Clock lost, selecting a new one"),
+        // The next entry of the playlist started
+        PlayerEvent::StreamStart => {
+            if let Some(playlist) = &playlist {
+                main_window.set_title(&playlist_title(&playlist.lock().unwrap()));
+            }
+        }
+        // Gapless transitions don't end the stream, this is the end of the media or the playlist
+        PlayerEvent::Eos => {
+            println!("End of stream reached");
+            // The recording got the EOS too, its file is complete
+            if let Some(record_button) = &record_button {
+                record_button.set_active(false);
+            }
+            let mut buffering = player_buffering.borrow_mut();
+            if let Err(err) = buffering::request_state(&pipeline, &mut buffering, gst::State::Ready)
+            {
+                eprintln!("{}", err);
+            }
+        }
+        // Missing plugins reported before are summarized rather than the generic error
+        PlayerEvent::Error(err) => println!("{}", err),
+        PlayerEvent::MissingPlugin(plugin) => println!("Missing plugin: {}", plugin),
+        PlayerEvent::StateChanged { current, .. } => {
+            println!("State set to {:?}", current);
+            if current == gst::State::Paused || current == gst::State::Playing {
+                // The streams are known once prerolled, the position is restored below
+                if let Some(state) = resume_from.borrow_mut().take() {
+                    if let Err(err) = state.restore_tracks(&pipeline) {
+                        eprintln!("{}", err);
+                    }
+                    seeker.borrow_mut().rate = state.rate;
+                    reconfigure_seek.set(Some(state.position()));
+                }
+                if let Some(position) = reconfigure_seek.take() {
+                    if let Err(err) = seeker.borrow().seek(&pipeline, position) {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            // The caps of the streams are known once prerolled
+            if current == gst::State::Paused {
+                streams_panel.refresh();
+            }
+        }
+        _ => (),
+    })
+}
+
+// Ask before the main window shows up, the media starts over unless the answer is yes
+fn ask_resume(state: &ResumeState) -> bool {
+    let dialog = MessageDialog::new(
+        None::<&Window>,
+        DialogFlags::MODAL,
+        MessageType::Question,
+        ButtonsType::YesNo,
+        &format!("Resume from {}?", cli::format_time(state.position())),
+    );
+    let response = dialog.run();
+    dialog.destroy();
+    response == ResponseType::Yes
+}
+
+// Previous and next buttons, shuffle toggle and repeat mode of the playlist
+fn create_playlist_controls(playbin: &gst::Element, playlist: Arc<Mutex<Playlist>>) -> Box {
+    let previous_button =
+        gtk::Button::new_from_icon_name(Some("media-skip-backward"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let previous_playlist = playlist.clone();
+    previous_button.connect_clicked(move |_| {
+        let uri = previous_playlist
+            .lock()
+            .unwrap()
+            .previous()
+            .map(|uri| uri.to_string());
+        if let Some(uri) = uri {
+            if let Err(err) = playlist::play_entry(&pipeline, &uri) {
+                eprintln!("{}", err);
+            }
+        }
+    });
+
+    let next_button =
+        gtk::Button::new_from_icon_name(Some("media-skip-forward"), gtk::IconSize::SmallToolbar);
+    let pipeline = playbin.clone();
+    let next_playlist = playlist.clone();
+    next_button.connect_clicked(move |_| {
+        let uri = next_playlist
+            .lock()
+            .unwrap()
+            .next()
+            .map(|uri| uri.to_string());
+        if let Some(uri) = uri {
+            if let Err(err) = playlist::play_entry(&pipeline, &uri) {
+                eprintln!("{}", err);
+            }
+        }
+    });
+
+    let shuffle_button = ToggleButton::new();
+    shuffle_button.set_image(Some(&Image::new_from_icon_name(
+        Some("media-playlist-shuffle"),
+        IconSize::SmallToolbar,
+    )));
+    shuffle_button.set_tooltip_text(Some("Shuffle"));
+    shuffle_button.set_active(playlist.lock().unwrap().is_shuffled());
+    let shuffle_playlist = playlist.clone();
+    shuffle_button.connect_toggled(move |button| {
+        shuffle_playlist
+            .lock()
+            .unwrap()
+            .set_shuffle(button.get_active());
+    });
+
+    let repeat_combo = ComboBoxText::new();
+    for mode in RepeatMode::ALL.iter() {
+        repeat_combo.append(Some(mode.name()), &format!("Repeat {}", mode.name()));
+    }
+    repeat_combo.set_active_id(Some(playlist.lock().unwrap().repeat_mode().name()));
+    repeat_combo.connect_changed(move |combo| {
+        if let Some(mode) = combo.get_active_id().and_then(|id| id.parse().ok()) {
+            playlist.lock().unwrap().set_repeat_mode(mode);
+        }
+    });
+
+    let playlist_controls = Box::new(Orientation::Horizontal, 0);
+    playlist_controls.pack_start(&previous_button, false, false, 0);
+    playlist_controls.pack_start(&next_button, false, false, 0);
+    playlist_controls.pack_start(&shuffle_button, false, false, 0);
+    playlist_controls.pack_start(&repeat_combo, false, false, 2);
+    playlist_controls
+}
+
+// Start recording what is played into a new file, or stop it. The file is finalized in the
+// background, the stopped recordings are put in `finalizing`.
+fn create_record_button(
+    main_window: &Window,
+    playbin: &gst::Element,
+    profile: Profile,
+    finalizing: Rc<RefCell<Finalizing>>,
+) -> ToggleButton {
+    let record_button = ToggleButton::new();
+    record_button.set_image(Some(&Image::new_from_icon_name(
+        Some("media-record"),
+        IconSize::SmallToolbar,
+    )));
+    record_button.set_tooltip_text(Some("Record"));
+
+    let recording: Rc<RefCell<Option<Recording>>> = Rc::new(RefCell::new(None));
+    let pipeline = playbin.clone();
+    let toggle_recording = recording.clone();
+    let toggle_finalizing = finalizing.clone();
+    record_button.connect_toggled(move |button| {
+        if !button.get_active() {
+            if let Some(recording) = toggle_recording.borrow_mut().take() {
+                let mut finalizing = toggle_finalizing.borrow_mut();
+                finalizing.push(recording.stop());
+                // After EOS nothing flows anymore, the file may be complete already
+                report_recordings(finalizing.finish_done());
+            }
+            button.set_tooltip_text(Some("Record"));
+            return;
+        }
+        let location = record::default_location(profile);
+        match Recording::start(&pipeline, &location, profile) {
+            Ok(started) => {
+                button.set_tooltip_text(Some(&format!("Recording to {}", location.display())));
+                *toggle_recording.borrow_mut() = Some(started);
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                button.set_active(false);
+            }
+        }
+    });
+
+    // The file is finalized before the pipeline stops, once the main loop returned
+    main_window.connect_delete_event(move |_, _| {
+        if let Some(recording) = recording.borrow_mut().take() {
+            finalizing.borrow_mut().push(recording.stop());
+        }
+        Inhibit(false)
+    });
+    record_button
+}
+
+fn report_recordings(finished: Vec<Result<PathBuf>>) {
+    for res in finished {
+        match res {
+            Ok(location) => println!("Recorded {}", location.display()),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+}
+
+// e.g. "2/5 - movie.webm"
+fn playlist_title(playlist: &Playlist) -> String {
+    let uri = playlist.current();
+    format!(
+        "{}/{} - {}",
+        playlist.current_index() + 1,
+        playlist.len(),
+        uri.rsplit('/').next().unwrap_or(uri)
+    )
+}
+
+// Pause when playing or waiting for the buffer to play, play otherwise
+fn toggle_pause(pipeline: &gst::Element, buffering: &mut Buffering) -> Result<()> {
+    let target = if buffering.target() == gst::State::Playing {
+        gst::State::Paused
+    } else {
+        gst::State::Playing
+    };
+    buffering::request_state(pipeline, buffering, target)
+}
+
+// Toggle, file chooser and font of the subtitles
+fn create_subtitle_controls(
+    main_window: &Window,
+    playbin: &gst::Element,
+    reconfigure_seek: Rc<Cell<Option<gst::ClockTime>>>,
+) -> Box {
+    let enabled_button = CheckButton::new_with_label("Subtitles");
+    enabled_button.set_active(subtitles::is_enabled(playbin));
+    let pipeline = playbin.clone();
+    enabled_button.connect_toggled(move |button| {
+        if let Err(err) = subtitles::set_enabled(&pipeline, button.get_active()) {
+            eprintln!("{}", err);
+        }
+    });
+
+    let open_button =
+        gtk::Button::new_from_icon_name(Some("document-open"), gtk::IconSize::SmallToolbar);
+    open_button.set_tooltip_text(Some("Load subtitles"));
+    let pipeline = playbin.clone();
+    let window = main_window.clone();
+    let enabled = enabled_button.clone();
+    open_button.connect_clicked(move |_| {
+        let file = match choose_subtitle_file(&window) {
+            Some(file) => file,
+            None => return,
+        };
+        match subtitles::load_external(&pipeline, &file) {
+            Ok(position) => {
+                reconfigure_seek.set(position);
+                enabled.set_active(true);
+            }
+            Err(err) => eprintln!("{}", err),
+        }
+    });
+
+    let font_button = FontButton::new();
+    font_button.set_tooltip_text(Some("Subtitle font"));
+    let pipeline = playbin.clone();
+    font_button.connect_font_set(move |font_button| {
+        if let Some(font_desc) = font_button.get_font() {
+            if let Err(err) = subtitles::set_font(&pipeline, &font_desc) {
+                eprintln!("{}", err);
+            }
+        }
+    });
+
+    let subtitle_controls = Box::new(Orientation::Horizontal, 0);
+    subtitle_controls.pack_start(&enabled_button, false, false, 0);
+    subtitle_controls.pack_start(&open_button, false, false, 0);
+    subtitle_controls.pack_start(&font_button, false, false, 0);
+    subtitle_controls
+}
+
+fn choose_subtitle_file(main_window: &Window) -> Option<String> {
+    let dialog = FileChooserDialog::with_buttons(
+        Some("Load subtitles"),
+        Some(main_window),
+        FileChooserAction::Open,
+        &[
+            ("_Cancel", ResponseType::Cancel),
+            ("_Open", ResponseType::Accept),
+        ],
+    );
+    let filter = FileFilter::new();
+    filter.set_name(Some("Subtitles"));
+    for extension in subtitles::EXTENSIONS {
+        filter.add_pattern(&format!("*.{}", extension));
+    }
+    dialog.add_filter(&filter);
+
+    let file = if dialog.run() == ResponseType::Accept {
+        dialog
+            .get_filename()
+            .map(|path| path.to_string_lossy().into_owned())
+    } else {
+        None
+    };
+    dialog.destroy();
+    file
+}
+
+// Rate in the top right corner of the video
+fn draw_rate(cr: &cairo::Context, letterbox: &Letterbox, rate: f64) {
+    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+    cr.set_font_size(letterbox.height / 12.0);
+    let text = format_rate(rate);
+    let extents = cr.text_extents(&text);
+    let margin = letterbox.height / 30.0;
+    cr.move_to(
+        letterbox.x + letterbox.width - extents.width - margin,
+        letterbox.y + extents.height + margin,
+    );
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.show_text(&text);
+}
+
+// e.g. "1.50x", or "-1.50x" when playing backward
+fn format_rate(rate: f64) -> String {
+    format!("{:.2}x", rate)
+}
+
+fn post_app_message(playbin: &gst::Element) {
+    /*
+    * API is under changing in new gstreamer-rs version.
+    After the new version relased, you may need to code like below.
+    let _ = playbin.post_message(&gst::message::Application::new(gst::Structure::new_empty(
+        "tags-changed",
+    )));
+    */
+    let _ = playbin.post_message(
+        &gst::message::Message::new_application(gst::Structure::new_empty("tags-changed")).build(),
+    );
+}
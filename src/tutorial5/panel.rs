@@ -0,0 +1,158 @@
+// Side panel listing every stream of playbin with its tags and caps, and selecting the active ones
+use gtk::*;
+
+use std::cell::RefCell;
+
+use crate::streams::{self, StreamInfo, STREAM_KINDS};
+use crate::subtitles;
+use crate::tutorial3::StreamKind;
+
+// Id of the entry of the text selector which disables subtitles
+const SUBTITLES_OFF: &str = "off";
+
+pub struct StreamsPanel {
+    playbin: gst::Element,
+    widget: ScrolledWindow,
+    content: Box,
+    // Streams currently shown, the panel is only rebuilt when they changed
+    shown: RefCell<Vec<StreamInfo>>,
+}
+
+impl StreamsPanel {
+    pub fn new(playbin: &gst::Element) -> StreamsPanel {
+        let content = Box::new(Orientation::Vertical, 4);
+        let widget = ScrolledWindow::new(None::<&Adjustment>, None::<&Adjustment>);
+        widget.set_policy(PolicyType::Never, PolicyType::Automatic);
+        widget.set_size_request(280, -1);
+        widget.add(&content);
+        StreamsPanel {
+            playbin: playbin.clone(),
+            widget,
+            content,
+            shown: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn widget(&self) -> &ScrolledWindow {
+        &self.widget
+    }
+
+    // Show the streams playbin knows about now. Caps are only known once the streams are linked.
+    pub fn refresh(&self) {
+        let infos: Vec<StreamInfo> = STREAM_KINDS
+            .iter()
+            .flat_map(|kind| streams::collect(&self.playbin, *kind))
+            .collect();
+        if *self.shown.borrow() == infos {
+            return;
+        }
+
+        for child in self.content.get_children() {
+            self.content.remove(&child);
+        }
+        for kind in STREAM_KINDS.iter().copied() {
+            let kind_infos: Vec<&StreamInfo> =
+                infos.iter().filter(|info| info.kind == kind).collect();
+            self.content
+                .pack_start(&self.kind_section(kind, &kind_infos), false, false, 4);
+        }
+        self.content.show_all();
+        *self.shown.borrow_mut() = infos;
+    }
+
+    // Header, selector of the active stream and details of every stream of a kind
+    fn kind_section(&self, kind: StreamKind, infos: &[&StreamInfo]) -> Box {
+        let section = Box::new(Orientation::Vertical, 2);
+        let header = Label::new(None);
+        header.set_markup(&format!(
+            "<b>{} streams: {}</b>",
+            kind_title(kind),
+            infos.len()
+        ));
+        header.set_halign(Align::Start);
+        section.pack_start(&header, false, false, 0);
+        if infos.is_empty() {
+            return section;
+        }
+
+        let selector = ComboBoxText::new();
+        // Subtitles can be turned off
+        if kind == StreamKind::Text {
+            selector.append(Some(SUBTITLES_OFF), "Off");
+        }
+        for info in infos {
+            selector.append(Some(&info.index.to_string()), &info.label());
+        }
+        match streams::current(&self.playbin, kind) {
+            Some(_) if kind == StreamKind::Text && !subtitles::is_enabled(&self.playbin) => {
+                selector.set_active_id(Some(SUBTITLES_OFF));
+            }
+            Some(current) => {
+                selector.set_active_id(Some(&current.to_string()));
+            }
+            None => (),
+        }
+        // Connected after setting the active stream, so that only the user switches streams
+        let playbin = self.playbin.clone();
+        selector.connect_changed(move |selector| {
+            let index = match selector.get_active_id() {
+                Some(id) => id.parse::<i32>().ok(),
+                None => return,
+            };
+            let res = match (kind, index) {
+                (StreamKind::Text, index) => subtitles::select_track(&playbin, index),
+                (_, Some(index)) => streams::select(&playbin, kind, index),
+                (_, None) => Ok(()),
+            };
+            if let Err(err) = res {
+                eprintln!("{}", err);
+            }
+        });
+        section.pack_start(&selector, false, false, 0);
+
+        for info in infos {
+            let expander = Expander::new(Some(&format!("Stream {}", info.index)));
+            let grid = Grid::new();
+            grid.set_column_spacing(8);
+            let mut row = 0;
+            add_rows(&grid, &mut row, "Tags", &info.tags);
+            add_rows(&grid, &mut row, "Caps", &info.caps);
+            expander.add(&grid);
+            section.pack_start(&expander, false, false, 0);
+        }
+        section
+    }
+}
+
+fn kind_title(kind: StreamKind) -> &'static str {
+    match kind {
+        StreamKind::Video => "Video",
+        StreamKind::Audio => "Audio",
+        StreamKind::Text => "Subtitle",
+    }
+}
+
+// A title row followed by one row per name and value
+fn add_rows(grid: &Grid, row: &mut i32, title: &str, rows: &[(String, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+    let title_label = Label::new(None);
+    title_label.set_markup(&format!("<i>{}</i>", title));
+    title_label.set_halign(Align::Start);
+    grid.attach(&title_label, 0, *row, 2, 1);
+    *row += 1;
+
+    for (name, value) in rows {
+        let name_label = Label::new(Some(name.as_str()));
+        name_label.set_halign(Align::Start);
+        name_label.set_valign(Align::Start);
+        let value_label = Label::new(Some(value.as_str()));
+        value_label.set_halign(Align::Start);
+        value_label.set_line_wrap(true);
+        value_label.set_selectable(true);
+        grid.attach(&name_label, 0, *row, 1, 1);
+        grid.attach(&value_label, 1, *row, 1, 1);
+        *row += 1;
+    }
+}
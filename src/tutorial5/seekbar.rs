@@ -0,0 +1,139 @@
+// Seek bar with elapsed and remaining time. While dragging, the target time is previewed and
+// fast key unit seeks are throttled, then a seek in the selected mode is done on release.
+use gtk::*;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::cli::format_time;
+use crate::seek::{SeekMode, Seeker};
+
+// Minimum time between two seeks while dragging
+const SCRUB_INTERVAL: Duration = Duration::from_millis(200);
+
+pub struct SeekBar {
+    widget: Box,
+    scale: Scale,
+    value_signal: glib::SignalHandlerId,
+    elapsed: Label,
+    remaining: Label,
+    dragging: Rc<Cell<bool>>,
+}
+
+impl SeekBar {
+    pub fn new(playbin: &gst::Element, seeker: Rc<RefCell<Seeker>>) -> SeekBar {
+        // Values are in seconds, with milliseconds precision
+        let scale = Scale::new_with_range(Orientation::Horizontal, 0.0, 1.0, 0.001);
+        scale.set_draw_value(false);
+        // Arrow keys move by 1s, page keys by 10s
+        scale.set_increments(1.0, 10.0);
+        let elapsed = Label::new(Some(&format_time(gst::CLOCK_TIME_NONE)));
+        elapsed.set_width_chars(10);
+        let remaining = Label::new(Some(&format_time(gst::CLOCK_TIME_NONE)));
+        remaining.set_width_chars(10);
+
+        let dragging = Rc::new(Cell::new(false));
+        let press_dragging = dragging.clone();
+        scale.connect_button_press_event(move |_, _| {
+            press_dragging.set(true);
+            Inhibit(false)
+        });
+
+        let pipeline = playbin.clone();
+        let value_seeker = seeker.clone();
+        let value_dragging = dragging.clone();
+        let preview = elapsed.clone();
+        let last_scrub: Cell<Option<Instant>> = Cell::new(None);
+        let value_signal = scale.connect_value_changed(move |scale| {
+            let target = seconds_to_time(scale.get_value());
+            if !value_dragging.get() {
+                // Keyboard, seek right away in the selected mode
+                if let Err(err) = value_seeker.borrow().seek(&pipeline, target) {
+                    eprintln!("{}", err);
+                }
+                return;
+            }
+
+            preview.set_markup(&format!("<b>{}</b>", format_time(target)));
+            let recent = last_scrub
+                .get()
+                .map(|last| last.elapsed() < SCRUB_INTERVAL)
+                .unwrap_or(false);
+            if recent {
+                return;
+            }
+            last_scrub.set(Some(Instant::now()));
+            let scrub = Seeker {
+                mode: SeekMode::KeyUnit,
+                ..*value_seeker.borrow()
+            };
+            if let Err(err) = scrub.seek(&pipeline, target) {
+                eprintln!("{}", err);
+            }
+        });
+
+        let pipeline = playbin.clone();
+        let release_dragging = dragging.clone();
+        scale.connect_button_release_event(move |scale, _| {
+            release_dragging.set(false);
+            if let Err(err) = seeker
+                .borrow()
+                .seek(&pipeline, seconds_to_time(scale.get_value()))
+            {
+                eprintln!("{}", err);
+            }
+            Inhibit(false)
+        });
+
+        let widget = Box::new(Orientation::Horizontal, 0);
+        widget.pack_start(&elapsed, false, false, 2);
+        widget.pack_start(&scale, true, true, 0);
+        widget.pack_start(&remaining, false, false, 2);
+        SeekBar {
+            widget,
+            scale,
+            value_signal,
+            elapsed,
+            remaining,
+            dragging,
+        }
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.widget
+    }
+
+    // Follow the playback, unless the user is dragging the bar
+    pub fn update(&self, position: gst::ClockTime, duration: gst::ClockTime) {
+        if self.dragging.get() {
+            return;
+        }
+
+        self.scale.block_signal(&self.value_signal);
+        if let Some(duration) = duration.nseconds() {
+            self.scale.set_range(0.0, duration as f64 / 1e9);
+        }
+        if let Some(position) = position.nseconds() {
+            self.scale.set_value(position as f64 / 1e9);
+        }
+        self.scale.unblock_signal(&self.value_signal);
+
+        self.elapsed.set_text(&format_time(position));
+        let remaining = match (position.nseconds(), duration.nseconds()) {
+            (Some(position), Some(duration)) => format!(
+                "-{}",
+                format_time(gst::ClockTime::from_nseconds(
+                    duration.saturating_sub(position)
+                ))
+            ),
+            _ => format_time(gst::CLOCK_TIME_NONE),
+        };
+        self.remaining.set_text(&remaining);
+    }
+}
+
+// Value of the scale, rounded to the millisecond
+fn seconds_to_time(seconds: f64) -> gst::ClockTime {
+    gst::ClockTime::from_mseconds((seconds.max(0.0) * 1000.0).round() as u64)
+}
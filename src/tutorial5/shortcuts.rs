@@ -0,0 +1,146 @@
+// Keyboard shortcuts and fullscreen mode of the GTK player
+use gdk::enums::key;
+use gtk::*;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+// How long the control bar stays visible in fullscreen once the mouse stopped moving
+const CONTROLS_TIMEOUT_MS: u32 = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shortcut {
+    PlayPause,
+    // Relative seek in seconds, backward when negative
+    Seek(i64),
+    // Change of the volume, which goes from 0 to 1 in the player
+    Volume(f64),
+    // Frames to step, backward when negative
+    Step(i64),
+    ToggleFullscreen,
+    LeaveFullscreen,
+}
+
+impl Shortcut {
+    // space: play/pause, left/right: -5s/+5s, with shift or page down/up: -60s/+60s,
+    // up/down: volume, f: fullscreen, escape: leave fullscreen, comma/period: step one frame
+    pub fn from_key(keyval: key::Key, state: gdk::ModifierType) -> Option<Shortcut> {
+        let long_seek = state.contains(gdk::ModifierType::SHIFT_MASK);
+        match keyval {
+            key::space => Some(Shortcut::PlayPause),
+            key::Left if long_seek => Some(Shortcut::Seek(-60)),
+            key::Right if long_seek => Some(Shortcut::Seek(60)),
+            key::Left => Some(Shortcut::Seek(-5)),
+            key::Right => Some(Shortcut::Seek(5)),
+            key::Page_Down => Some(Shortcut::Seek(-60)),
+            key::Page_Up => Some(Shortcut::Seek(60)),
+            key::Up => Some(Shortcut::Volume(0.05)),
+            key::Down => Some(Shortcut::Volume(-0.05)),
+            key::f | key::F => Some(Shortcut::ToggleFullscreen),
+            key::Escape => Some(Shortcut::LeaveFullscreen),
+            key::comma => Some(Shortcut::Step(-1)),
+            key::period => Some(Shortcut::Step(1)),
+            _ => None,
+        }
+    }
+}
+
+// Fullscreen state of the main window. The stream panel is hidden in fullscreen, the control bar
+// shows up while the mouse moves and hides again after CONTROLS_TIMEOUT_MS.
+pub struct Fullscreen {
+    window: Window,
+    controls: Widget,
+    panel: Widget,
+    active: Cell<bool>,
+    hide_source: Rc<Cell<Option<glib::SourceId>>>,
+}
+
+impl Fullscreen {
+    pub fn new(window: &Window, controls: &Widget, panel: &Widget) -> Rc<Fullscreen> {
+        let fullscreen = Rc::new(Fullscreen {
+            window: window.clone(),
+            controls: controls.clone(),
+            panel: panel.clone(),
+            active: Cell::new(false),
+            hide_source: Rc::new(Cell::new(None)),
+        });
+
+        // Follow the window manager, which may also enter or leave fullscreen by itself
+        let weak = Rc::downgrade(&fullscreen);
+        window.connect_window_state_event(move |_, event| {
+            if let Some(fullscreen) = weak.upgrade() {
+                fullscreen.set_active(
+                    event
+                        .get_new_window_state()
+                        .contains(gdk::WindowState::FULLSCREEN),
+                );
+            }
+            Inhibit(false)
+        });
+
+        window.add_events(gdk::EventMask::POINTER_MOTION_MASK);
+        let weak = Rc::downgrade(&fullscreen);
+        window.connect_motion_notify_event(move |_, _| {
+            if let Some(fullscreen) = weak.upgrade() {
+                if fullscreen.active.get() {
+                    fullscreen.show_controls();
+                }
+            }
+            Inhibit(false)
+        });
+        fullscreen
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.get()
+    }
+
+    pub fn toggle(&self) {
+        if self.active.get() {
+            self.window.unfullscreen();
+        } else {
+            self.window.fullscreen();
+        }
+    }
+
+    pub fn leave(&self) {
+        if self.active.get() {
+            self.window.unfullscreen();
+        }
+    }
+
+    fn set_active(&self, active: bool) {
+        if active == self.active.get() {
+            return;
+        }
+        self.active.set(active);
+        if active {
+            self.panel.hide();
+            self.show_controls();
+        } else {
+            self.cancel_hide();
+            self.panel.show();
+            self.controls.show();
+        }
+    }
+
+    // Show the control bar until the mouse stays still for CONTROLS_TIMEOUT_MS
+    fn show_controls(&self) {
+        self.controls.show();
+        self.cancel_hide();
+        let controls = self.controls.clone();
+        let hide_source = self.hide_source.clone();
+        let source = glib::timeout_add_local(CONTROLS_TIMEOUT_MS, move || {
+            hide_source.set(None);
+            controls.hide();
+            glib::Continue(false)
+        });
+        self.hide_source.set(Some(source));
+    }
+
+    fn cancel_hide(&self) {
+        if let Some(source) = self.hide_source.take() {
+            glib::source_remove(source);
+        }
+    }
+}
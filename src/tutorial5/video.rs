@@ -0,0 +1,398 @@
+// Video output of the GTK player: native X11 overlay, gtkglsink/gtksink widget,
+// or frames pulled from an appsink and drawn in a DrawingArea with our own overlays.
+use gdk::GdkContextExt;
+use gst::prelude::*;
+use gst_video::prelude::*;
+use gtk::*;
+
+use std::cell::RefCell;
+use std::os::raw::c_void;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use glib::object::ObjectType;
+
+use crate::error::{self, Error, Result};
+
+// Which element draws the video into the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Renderer {
+    // Overlay on X11, the GTK sink widget elsewhere
+    Auto,
+    // Native X11 window handed to playbin's video overlay
+    Overlay,
+    // gtkglsink or gtksink widget, works on any GDK backend
+    GtkSink,
+    // RGBA frames pulled from an appsink and painted with Cairo
+    AppSink,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::Auto
+    }
+}
+
+impl Renderer {
+    pub const ALL: [Renderer; 4] = [
+        Renderer::Auto,
+        Renderer::Overlay,
+        Renderer::GtkSink,
+        Renderer::AppSink,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Auto => "auto",
+            Renderer::Overlay => "overlay",
+            Renderer::GtkSink => "gtksink",
+            Renderer::AppSink => "appsink",
+        }
+    }
+}
+
+impl FromStr for Renderer {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Renderer> {
+        Renderer::ALL
+            .iter()
+            .copied()
+            .find(|renderer| renderer.name() == s)
+            .ok_or_else(|| Error::Usage(format!("Unknown renderer {}", s)))
+    }
+}
+
+// Rectangle a frame is drawn in, scaled to fit the area with its aspect ratio kept and centered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Letterbox {
+    pub fn fit(
+        frame_width: f64,
+        frame_height: f64,
+        area_width: f64,
+        area_height: f64,
+    ) -> Letterbox {
+        let scale = (area_width / frame_width).min(area_height / frame_height);
+        let width = frame_width * scale;
+        let height = frame_height * scale;
+        Letterbox {
+            x: (area_width - width) / 2.0,
+            y: (area_height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+}
+
+// Widget showing the video, with the renderer drawing into it for Renderer::AppSink
+pub struct VideoOutput {
+    pub widget: gtk::Widget,
+    pub frame_renderer: Option<FrameRenderer>,
+}
+
+impl VideoOutput {
+    // The video sink opens its own window, the widget stays empty
+    fn external() -> VideoOutput {
+        VideoOutput {
+            widget: DrawingArea::new().upcast(),
+            frame_renderer: None,
+        }
+    }
+}
+
+// Create the video widget and set playbin's video sink for the renderer.
+// Must be called before playing.
+pub fn create(playbin: &gst::Element, renderer: Renderer) -> VideoOutput {
+    // Synthetic sources are not played by playbin, their sink can't be replaced
+    let video_overlay = match playbin.clone().dynamic_cast::<gst_video::VideoOverlay>() {
+        Ok(video_overlay) => video_overlay,
+        Err(_) => {
+            println!("Video is rendered in a separate window for this source");
+            return VideoOutput::external();
+        }
+    };
+
+    let display_type_name = gdk::Display::get_default().map(|display| display.get_type().name());
+    let is_x11 = display_type_name.as_deref() == Some("GdkX11Display");
+    let renderer = match renderer {
+        // Window handles only exist on X11, Wayland and other backends use a sink widget
+        Renderer::Auto if is_x11 => Renderer::Overlay,
+        Renderer::Auto => Renderer::GtkSink,
+        renderer => renderer,
+    };
+
+    match renderer {
+        Renderer::Overlay if is_x11 => overlay_output(video_overlay),
+        Renderer::Overlay => {
+            println!(
+                "The overlay needs X11, drawing the frames from an appsink on {:?}",
+                display_type_name
+            );
+            appsink_output(playbin)
+        }
+        Renderer::GtkSink => match sink_widget_output(playbin) {
+            Ok(output) => output,
+            Err(err) => {
+                println!("{}, drawing the frames from an appsink", err);
+                appsink_output(playbin)
+            }
+        },
+        _ => appsink_output(playbin),
+    }
+}
+
+fn overlay_output(video_overlay: gst_video::VideoOverlay) -> VideoOutput {
+    let video_window = DrawingArea::new();
+    video_window.connect_realize(move |video_window| {
+        let gdk_window = video_window.get_window().unwrap();
+
+        if !gdk_window.ensure_native() {
+            eprintln!("Can't create native window for widget");
+            return;
+        }
+
+        extern "C" {
+            pub fn gdk_x11_window_get_xid(window: *mut glib::object::GObject) -> *mut c_void;
+        }
+
+        #[allow(clippy::cast_ptr_alignment)]
+        unsafe {
+            // Call native API to obtain the window pointer
+            let xid = gdk_x11_window_get_xid(gdk_window.as_ptr() as *mut _);
+            // Set destination with the handler
+            video_overlay.set_window_handle(xid as usize);
+        }
+    });
+    VideoOutput {
+        widget: video_window.upcast(),
+        frame_renderer: None,
+    }
+}
+
+fn sink_widget_output(playbin: &gst::Element) -> Result<VideoOutput> {
+    let (sink, widget) = make_sink_widget()?;
+    error::set_property(playbin, "video-sink", &sink)?;
+    Ok(VideoOutput {
+        widget,
+        frame_renderer: None,
+    })
+}
+
+// Prefer gtkglsink wrapped in glsinkbin for the GL upload and conversion, fall back to gtksink
+fn make_sink_widget() -> Result<(gst::Element, gtk::Widget)> {
+    let (sink, widget_owner) = match error::make_element("gtkglsink", None) {
+        Ok(gtkglsink) => {
+            let glsinkbin = error::make_element("glsinkbin", None)?;
+            error::set_property(&glsinkbin, "sink", &gtkglsink)?;
+            (glsinkbin, gtkglsink)
+        }
+        Err(_) => {
+            let gtksink = error::make_element("gtksink", None)?;
+            (gtksink.clone(), gtksink)
+        }
+    };
+    // Both sinks create their widget on construction
+    let widget = widget_owner
+        .get_property("widget")
+        .ok()
+        .and_then(|widget| widget.get::<gtk::Widget>().ok().flatten())
+        .ok_or_else(|| Error::Property {
+            element: widget_owner.get_name().to_string(),
+            property: "widget".to_string(),
+        })?;
+    Ok((sink, widget))
+}
+
+fn appsink_output(playbin: &gst::Element) -> VideoOutput {
+    let area = DrawingArea::new();
+    let frame_renderer = FrameRenderer::new(&area).and_then(|frame_renderer| {
+        error::set_property(playbin, "video-sink", frame_renderer.sink())?;
+        Ok(frame_renderer)
+    });
+    match frame_renderer {
+        Ok(frame_renderer) => VideoOutput {
+            widget: area.upcast(),
+            frame_renderer: Some(frame_renderer),
+        },
+        Err(err) => {
+            eprintln!("{}, the video is rendered in a separate window", err);
+            VideoOutput::external()
+        }
+    }
+}
+
+// Decoded RGBA frame copied out of its sample, so that it can be sent to the main thread
+struct Frame {
+    data: Vec<u8>,
+    width: i32,
+    height: i32,
+    pixel_aspect_ratio: f64,
+}
+
+impl Frame {
+    fn from_sample(sample: &gst::Sample) -> Option<Frame> {
+        let caps = sample.get_caps()?;
+        let structure = caps.get_structure(0)?;
+        let width = structure.get_some::<i32>("width").ok()?;
+        let height = structure.get_some::<i32>("height").ok()?;
+        let pixel_aspect_ratio = structure
+            .get_some::<gst::Fraction>("pixel-aspect-ratio")
+            .map(|par| f64::from(*par.numer()) / f64::from(*par.denom()))
+            .unwrap_or(1.0);
+
+        let buffer = sample.get_buffer()?;
+        let map = buffer.map_readable().ok()?;
+        // RGBA rows are always 4 bytes aligned, there is no padding
+        if map.as_slice().len() < (width * height * 4) as usize {
+            return None;
+        }
+        Some(Frame {
+            data: map.as_slice().to_vec(),
+            width,
+            height,
+            pixel_aspect_ratio,
+        })
+    }
+}
+
+// Frame being displayed, converted on the main thread
+struct Picture {
+    pixbuf: gdk_pixbuf::Pixbuf,
+    pixel_aspect_ratio: f64,
+}
+
+impl From<Frame> for Picture {
+    fn from(frame: Frame) -> Picture {
+        let pixbuf = gdk_pixbuf::Pixbuf::new_from_mut_slice(
+            frame.data,
+            gdk_pixbuf::Colorspace::Rgb,
+            true,
+            8,
+            frame.width,
+            frame.height,
+            frame.width * 4,
+        );
+        Picture {
+            pixbuf,
+            pixel_aspect_ratio: frame.pixel_aspect_ratio,
+        }
+    }
+}
+
+// std Box, gtk::Box is imported by the glob
+type Overlay = std::boxed::Box<dyn Fn(&cairo::Context, &Letterbox)>;
+
+// Draws the frames of an appsink in a DrawingArea, with black bars around them
+pub struct FrameRenderer {
+    sink: gst::Element,
+    overlays: Rc<RefCell<Vec<Overlay>>>,
+}
+
+impl FrameRenderer {
+    pub fn new(area: &DrawingArea) -> Result<FrameRenderer> {
+        let sink = error::make_element("appsink", Some("video-renderer"))?;
+        let appsink = sink
+            .clone()
+            .dynamic_cast::<gst_app::AppSink>()
+            .expect("appsink is not an AppSink");
+        // GdkPixbuf takes RGBA, playbin converts the frames before the sink
+        appsink.set_caps(Some(&gst::Caps::new_simple(
+            "video/x-raw",
+            &[("format", &"RGBA")],
+        )));
+        // Only the latest frame matters, drop the old ones when drawing is late
+        appsink.set_max_buffers(1);
+        appsink.set_drop(true);
+
+        // Latest frame not drawn yet. The streaming thread only wakes the main thread up
+        // when it was taken, so that frames never pile up in the channel.
+        let pending = Arc::new(Mutex::new(None));
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let streaming_pending = pending.clone();
+        appsink.set_callbacks(
+            gst_app::AppSinkCallbacks::new()
+                .new_sample(move |appsink| {
+                    let sample = appsink.pull_sample().map_err(|_| gst::FlowError::Eos)?;
+                    let frame = Frame::from_sample(&sample).ok_or(gst::FlowError::Error)?;
+                    if streaming_pending.lock().unwrap().replace(frame).is_none() {
+                        let _ = sender.send(());
+                    }
+                    Ok(gst::FlowSuccess::Ok)
+                })
+                .build(),
+        );
+
+        let picture: Rc<RefCell<Option<Picture>>> = Rc::new(RefCell::new(None));
+        let received_picture = picture.clone();
+        let area_weak = area.downgrade();
+        receiver.attach(None, move |()| {
+            let area = match area_weak.upgrade() {
+                Some(area) => area,
+                None => return glib::Continue(false),
+            };
+            if let Some(frame) = pending.lock().unwrap().take() {
+                *received_picture.borrow_mut() = Some(Picture::from(frame));
+                area.queue_draw();
+            }
+            glib::Continue(true)
+        });
+
+        let overlays: Rc<RefCell<Vec<Overlay>>> = Rc::new(RefCell::new(Vec::new()));
+        let draw_overlays = overlays.clone();
+        area.connect_draw(move |area, cr| {
+            let area_width = f64::from(area.get_allocated_width());
+            let area_height = f64::from(area.get_allocated_height());
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            cr.paint();
+
+            let picture = picture.borrow();
+            let picture = match &*picture {
+                Some(picture) => picture,
+                None => return Inhibit(false),
+            };
+            let pixbuf_width = f64::from(picture.pixbuf.get_width());
+            let pixbuf_height = f64::from(picture.pixbuf.get_height());
+            let letterbox = Letterbox::fit(
+                pixbuf_width * picture.pixel_aspect_ratio,
+                pixbuf_height,
+                area_width,
+                area_height,
+            );
+
+            cr.save();
+            cr.translate(letterbox.x, letterbox.y);
+            cr.scale(
+                letterbox.width / pixbuf_width,
+                letterbox.height / pixbuf_height,
+            );
+            cr.set_source_pixbuf(&picture.pixbuf, 0.0, 0.0);
+            cr.paint();
+            cr.restore();
+
+            for overlay in draw_overlays.borrow().iter() {
+                overlay(cr, &letterbox);
+            }
+            Inhibit(false)
+        });
+
+        Ok(FrameRenderer { sink, overlays })
+    }
+
+    // The appsink to set as playbin's video-sink
+    pub fn sink(&self) -> &gst::Element {
+        &self.sink
+    }
+
+    // Draw over every frame, in the coordinates of the area
+    pub fn add_overlay<F: Fn(&cairo::Context, &Letterbox) + 'static>(&self, draw: F) {
+        self.overlays.borrow_mut().push(std::boxed::Box::new(draw));
+    }
+}
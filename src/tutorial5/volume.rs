@@ -0,0 +1,134 @@
+// Volume slider, mute toggle and audio output selection of the GTK player
+use gtk::*;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::audio;
+use crate::error::Result;
+use crate::player;
+use crate::settings::Settings;
+
+pub struct AudioControls {
+    widget: Box,
+    volume_scale: Scale,
+    volume_signal: glib::SignalHandlerId,
+    mute_button: ToggleButton,
+    mute_signal: glib::SignalHandlerId,
+}
+
+impl AudioControls {
+    // Changes are recorded in `settings`. Switching the sink while playing restarts playbin,
+    // `reconfigure_seek` receives the position to seek back to.
+    pub fn new(
+        playbin: &gst::Element,
+        settings: Rc<RefCell<Settings>>,
+        reconfigure_seek: Rc<Cell<Option<gst::ClockTime>>>,
+    ) -> AudioControls {
+        let mute_button = ToggleButton::new();
+        mute_button.set_image(Some(&Image::new_from_icon_name(
+            Some("audio-volume-muted"),
+            IconSize::SmallToolbar,
+        )));
+        mute_button.set_tooltip_text(Some("Mute"));
+        mute_button.set_active(audio::is_muted(playbin));
+        let pipeline = playbin.clone();
+        let mute_settings = settings.clone();
+        let mute_signal = mute_button.connect_toggled(move |button| {
+            if let Err(err) = audio::set_muted(&pipeline, button.get_active()) {
+                eprintln!("{}", err);
+            }
+            mute_settings.borrow_mut().set("mute", button.get_active());
+        });
+
+        let volume_scale =
+            Scale::new_with_range(Orientation::Horizontal, 0.0, audio::MAX_VOLUME, 0.05);
+        volume_scale.set_draw_value(false);
+        volume_scale.set_size_request(100, -1);
+        volume_scale.set_value(audio::volume(playbin));
+        let pipeline = playbin.clone();
+        let volume_settings = settings.clone();
+        let volume_signal = volume_scale.connect_value_changed(move |scale| {
+            if let Err(err) = audio::set_volume(&pipeline, scale.get_value()) {
+                eprintln!("{}", err);
+            }
+            volume_settings
+                .borrow_mut()
+                .set("volume", scale.get_value());
+        });
+
+        let sink_combo = ComboBoxText::new();
+        for sink in audio::available_sinks() {
+            sink_combo.append(Some(sink), sink);
+        }
+        let current_sink = settings
+            .borrow()
+            .get("audio-sink")
+            .unwrap_or("autoaudiosink")
+            .to_string();
+        sink_combo.set_active_id(Some(&current_sink));
+        let pipeline = playbin.clone();
+        sink_combo.connect_changed(move |combo| {
+            let sink = match combo.get_active_id() {
+                Some(sink) => sink.to_string(),
+                None => return,
+            };
+            match player::reconfigure(&pipeline, || audio::set_sink(&pipeline, &sink)) {
+                Ok(position) => {
+                    reconfigure_seek.set(position);
+                    settings.borrow_mut().set("audio-sink", &sink);
+                }
+                Err(err) => eprintln!("{}", err),
+            }
+        });
+
+        let widget = Box::new(Orientation::Horizontal, 0);
+        widget.pack_start(&mute_button, false, false, 0);
+        widget.pack_start(&volume_scale, false, false, 0);
+        widget.pack_start(&sink_combo, false, false, 2);
+        AudioControls {
+            widget,
+            volume_scale,
+            volume_signal,
+            mute_button,
+            mute_signal,
+        }
+    }
+
+    pub fn widget(&self) -> &Box {
+        &self.widget
+    }
+
+    // Move the volume scale by `delta`, within its range, as if the user dragged it
+    pub fn change_volume(&self, delta: f64) {
+        self.volume_scale
+            .set_value(self.volume_scale.get_value() + delta);
+    }
+
+    // Follow changes made outside of the controls, e.g. by the audio sink,
+    // without setting them back on playbin
+    pub fn update(&self, volume: f64, muted: bool) {
+        self.volume_scale.block_signal(&self.volume_signal);
+        self.volume_scale.set_value(volume);
+        self.volume_scale.unblock_signal(&self.volume_signal);
+
+        self.mute_button.block_signal(&self.mute_signal);
+        self.mute_button.set_active(muted);
+        self.mute_button.unblock_signal(&self.mute_signal);
+    }
+}
+
+// Restore the volume, mute and audio sink of the previous run. Must be called before playing.
+pub fn apply_settings(playbin: &gst::Element, settings: &Settings) -> Result<()> {
+    // A saved volume above the slider, e.g. set through the audio sink, is lowered to its range
+    if let Some(volume) = settings.parse::<f64>("volume") {
+        audio::set_volume(playbin, volume.min(audio::MAX_VOLUME))?;
+    }
+    if let Some(muted) = settings.parse::<bool>("mute") {
+        audio::set_muted(playbin, muted)?;
+    }
+    if let Some(sink) = settings.get("audio-sink") {
+        audio::set_sink(playbin, sink)?;
+    }
+    Ok(())
+}
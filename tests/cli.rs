@@ -0,0 +1,153 @@
+// Command line parsing of the tutorial binaries
+extern crate gstreamer as gst;
+
+use gstreamer_rust::cli::{self, Args};
+use gstreamer_rust::media::{self, MediaSource};
+use gstreamer_rust::seek::{self, SeekMode};
+use gstreamer_rust::subtitles;
+use gstreamer_rust::tutorial4::{AbLoop, Options};
+
+fn args(args: &[&str], switches: &[&str]) -> Args {
+    Args::parse(args.iter().map(|a| a.to_string()), switches)
+}
+
+#[test]
+fn options_are_not_taken_as_media() {
+    let args = args(
+        &[
+            "--loop-start",
+            "10",
+            "https://example.com/a.webm",
+            "--no-loop",
+        ],
+        &["no-loop"],
+    );
+    assert_eq!(
+        MediaSource::from_cli(&args),
+        MediaSource::Uri("https://example.com/a.webm".to_string())
+    );
+    assert_eq!(args.get("loop-start"), Some("10"));
+    assert!(args.has("no-loop"));
+}
+
+#[test]
+fn synthetic_sources_are_recognized() {
+    assert_eq!(
+        MediaSource::from_cli(&args(&["--uri=videotestsrc"], &[])),
+        MediaSource::VideoTest
+    );
+    assert_eq!(MediaSource::parse("audiotestsrc"), MediaSource::AudioTest);
+}
+
+#[test]
+fn times_are_parsed_in_seconds_or_minutes() {
+    assert_eq!(cli::parse_time("95"), Some(95 * gst::SECOND));
+    assert_eq!(cli::parse_time("1:35.5"), Some(95_500 * gst::MSECOND));
+    assert_eq!(cli::parse_time("abc"), None);
+    assert_eq!(cli::parse_time("-3"), None);
+}
+
+#[test]
+fn times_are_formatted_to_the_millisecond() {
+    assert_eq!(cli::format_time(95_500 * gst::MSECOND), "1:35.500");
+    assert_eq!(cli::format_time(3723 * gst::SECOND), "1:02:03.000");
+    assert_eq!(cli::format_time(gst::CLOCK_TIME_NONE), "--:--");
+    let time = cli::format_time(95_500 * gst::MSECOND);
+    assert_eq!(cli::parse_time(&time), Some(95_500 * gst::MSECOND));
+}
+
+#[test]
+fn ab_loop_is_configurable() {
+    let ab_loop = AbLoop::from_args(&args(
+        &["--loop-start", "1:00", "--loop-end=1:10", "--repeat", "2"],
+        &[],
+    ))
+    .unwrap()
+    .unwrap();
+    assert_eq!(ab_loop.start, 60 * gst::SECOND);
+    assert_eq!(ab_loop.end, 70 * gst::SECOND);
+    assert_eq!(ab_loop.repeat, Some(2));
+
+    assert_eq!(
+        AbLoop::from_args(&args(&[], &[])).unwrap(),
+        Some(AbLoop::default())
+    );
+    assert_eq!(
+        AbLoop::from_args(&args(&["--no-loop"], &["no-loop"])).unwrap(),
+        None
+    );
+    assert!(AbLoop::from_args(&args(&["--loop-end", "10"], &[])).is_err());
+}
+
+#[test]
+fn resume_is_offered_without_explicit_loop() {
+    let options = Options::from_args(&args(&[], &[])).unwrap();
+    assert_eq!(options.ab_loop, Some(AbLoop::default()));
+    assert!(!options.explicit_loop);
+    assert!(
+        Options::from_args(&args(&["--loop-start", "10"], &[]))
+            .unwrap()
+            .explicit_loop
+    );
+    assert!(
+        Options::from_args(&args(&["--repeat=3"], &[]))
+            .unwrap()
+            .explicit_loop
+    );
+}
+
+#[test]
+fn seek_mode_is_selectable() {
+    let options = Options::from_args(&args(&["--seek-mode", "snap-after"], &[])).unwrap();
+    assert_eq!(options.seek_mode, SeekMode::SnapAfter);
+    assert_eq!(
+        Options::from_args(&args(&[], &[])).unwrap().seek_mode,
+        SeekMode::KeyUnit
+    );
+    assert!(Options::from_args(&args(&["--seek-mode", "fast"], &[])).is_err());
+}
+
+#[test]
+fn relative_seeks_stay_within_the_media() {
+    let duration = 60 * gst::SECOND;
+    assert_eq!(
+        seek::offset_position(10 * gst::SECOND, duration, 5),
+        15 * gst::SECOND
+    );
+    assert_eq!(
+        seek::offset_position(3 * gst::SECOND, duration, -5),
+        0 * gst::SECOND
+    );
+    assert_eq!(
+        seek::offset_position(30 * gst::SECOND, duration, 60),
+        duration
+    );
+    assert_eq!(
+        seek::offset_position(30 * gst::SECOND, gst::CLOCK_TIME_NONE, 60),
+        90 * gst::SECOND
+    );
+}
+
+#[test]
+fn playback_rate_is_validated() {
+    let options = Options::from_args(&args(&["--rate", "-2"], &[])).unwrap();
+    assert_eq!(options.rate, -2.0);
+    assert_eq!(Options::from_args(&args(&[], &[])).unwrap().rate, 1.0);
+    assert!(Options::from_args(&args(&["--rate", "10"], &[])).is_err());
+    assert!(Options::from_args(&args(&["--rate", "0"], &[])).is_err());
+}
+
+#[test]
+fn subtitle_files_are_recognized() {
+    assert!(subtitles::is_subtitle_file("movie.srt"));
+    assert!(subtitles::is_subtitle_file("/tmp/Movie.en.VTT"));
+    assert!(subtitles::is_subtitle_file("movie.ass"));
+    assert!(!subtitles::is_subtitle_file("movie.webm"));
+    assert!(!subtitles::is_subtitle_file("srt"));
+
+    assert_eq!(
+        media::to_uri("https://example.com/a.vtt"),
+        "https://example.com/a.vtt"
+    );
+    assert!(media::to_uri("a.srt").starts_with("file:///"));
+}
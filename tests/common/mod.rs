@@ -0,0 +1,178 @@
+// Helpers shared by the headless integration tests.
+// Pipelines are played into fakesink/appsink, so no display or audio device is needed.
+#![allow(dead_code)]
+
+extern crate gstreamer as gst;
+
+use gst::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+static FIXTURE: Once = Once::new();
+
+// Length of the generated fixture, long enough for the 30s - 35s loop of tutorial4
+pub const FIXTURE_SECONDS: u64 = 40;
+
+pub fn init() {
+    gst::init().unwrap();
+}
+
+// Everything interesting which was posted on the bus while running a pipeline
+#[derive(Debug, Default)]
+pub struct BusReport {
+    pub eos: bool,
+    pub errors: Vec<String>,
+    pub states: Vec<gst::State>,
+}
+
+impl BusReport {
+    pub fn reached(&self, state: gst::State) -> bool {
+        self.states.contains(&state)
+    }
+}
+
+// Play the pipeline until EOS, an error or the timeout, and collect the bus messages
+pub fn run_to_eos(pipeline: &gst::Element, timeout: Duration) -> BusReport {
+    let mut report = BusReport::default();
+    let bus = pipeline.get_bus().unwrap();
+    pipeline
+        .set_state(gst::State::Playing)
+        .expect("Unable to set the pipeline to the `Playing` state");
+
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let msg = match bus.timed_pop(100 * gst::MSECOND) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        match msg.view() {
+            gst::MessageView::Error(err) => {
+                report.errors.push(format!(
+                    "{:?}: {} ({:?})",
+                    err.get_src().map(|s| s.get_path_string()),
+                    err.get_error(),
+                    err.get_debug()
+                ));
+                break;
+            }
+            gst::MessageView::Eos(..) => {
+                report.eos = true;
+                break;
+            }
+            gst::MessageView::StateChanged(state_changed) => {
+                if state_changed
+                    .get_src()
+                    .map(|s| s == *pipeline)
+                    .unwrap_or(false)
+                {
+                    report.states.push(state_changed.get_current());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pipeline
+        .set_state(gst::State::Null)
+        .expect("Unable to set the pipeline to the `Null` state");
+    report
+}
+
+pub fn fake_sink(name: &str, sync: bool) -> gst::Element {
+    fake_sink_with_name(Some(name), sync)
+}
+
+pub fn fake_sink_with_name(name: Option<&str>, sync: bool) -> gst::Element {
+    let sink = gst::ElementFactory::make("fakesink", name).unwrap();
+    sink.set_property("sync", &sync).unwrap();
+    sink
+}
+
+// Replace the autovideosink/autoaudiosink chosen by playbin with fakesinks
+pub fn use_fake_sinks(playbin: &gst::Element, sync: bool) {
+    playbin
+        .set_property("video-sink", &fake_sink("videosink", sync))
+        .unwrap();
+    playbin
+        .set_property("audio-sink", &fake_sink("audiosink", sync))
+        .unwrap();
+}
+
+// Limit the number of buffers produced by a synthetic source, so that it reaches EOS
+pub fn limit_buffers(pipeline: &gst::Pipeline, source_name: &str, num_buffers: i32) {
+    pipeline
+        .get_by_name(source_name)
+        .expect("Source element not found")
+        .set_property("num-buffers", &num_buffers)
+        .unwrap();
+}
+
+// Path of a small Ogg (Theora + Vorbis) file encoded from the test sources.
+// It is generated once per test binary, so no network access or media file is needed.
+pub fn fixture_path() -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("gstreamer-rust-fixture-{}.ogg", std::process::id()));
+    let location = path.clone();
+    FIXTURE.call_once(move || {
+        init();
+        let description = format!(
+            "videotestsrc num-buffers={video_buffers} \
+             ! video/x-raw,width=160,height=120,framerate=10/1 \
+             ! theoraenc keyframe-max-distance=10 ! queue ! oggmux name=mux \
+             ! filesink location=\"{location}\" \
+             audiotestsrc num-buffers={audio_buffers} ! audioconvert ! vorbisenc ! queue ! mux.",
+            video_buffers = FIXTURE_SECONDS * 10,
+            audio_buffers = FIXTURE_SECONDS * 44100 / 1024,
+            location = location.display()
+        );
+        let pipeline = gst::parse_launch(&description).expect("Could not build fixture pipeline");
+        let report = run_to_eos(&pipeline, Duration::from_secs(60));
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(report.eos, "Fixture encoding didn't finish");
+    });
+    path
+}
+
+pub fn fixture_uri() -> String {
+    glib::filename_to_uri(fixture_path(), None)
+        .unwrap()
+        .to_string()
+}
+
+// Stand-in for a network server: serve a file over HTTP on localhost, for as long as the test runs.
+// Returns the URL of the file. Ranges are not supported, so the stream isn't seekable.
+pub fn serve_file(path: &Path) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Could not bind local HTTP server");
+    let port = listener.local_addr().unwrap().port();
+    let content = std::fs::read(path).expect("Could not read served file");
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // Skip the request, every path gets the file
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).map(|n| n > 0).unwrap_or(false) {
+                if line == "\r\n" {
+                    break;
+                }
+                line.clear();
+            }
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/ogg\r\n\
+                 Content-Length: {}\r\nAccept-Ranges: none\r\nConnection: close\r\n\r\n",
+                content.len()
+            );
+            // The client may close the connection early, e.g. when the test is over
+            let _ = stream
+                .write_all(header.as_bytes())
+                .and_then(|_| stream.write_all(&content));
+        }
+    });
+    format!("http://127.0.0.1:{}/fixture.ogg", port)
+}
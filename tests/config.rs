@@ -0,0 +1,197 @@
+// Pipelines described in TOML and YAML files
+extern crate gstreamer as gst;
+
+mod common;
+
+use gst::prelude::*;
+use gstreamer_rust::config::{self, EosPolicy, ErrorPolicy, PipelineConfig, PropertyValue};
+use gstreamer_rust::error::Error;
+use gstreamer_rust::streams;
+use std::path::Path;
+
+fn example(name: &str) -> PipelineConfig {
+    PipelineConfig::load(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("pipelines")
+            .join(name),
+    )
+    .unwrap()
+}
+
+#[test]
+fn example_descriptions_are_parsed() {
+    let tutorial2 = example("tutorial2.toml");
+    assert_eq!(tutorial2.name.as_deref(), Some("test-manual-pipeline"));
+    assert_eq!(tutorial2.factories(), vec!["videotestsrc", "autovideosink"]);
+    assert_eq!(
+        tutorial2.elements[0].properties["num-buffers"],
+        PropertyValue::Int(300)
+    );
+    assert_eq!(tutorial2.bus.on_error, ErrorPolicy::Stop);
+
+    let tutorial3 = example("tutorial3.yaml");
+    assert_eq!(tutorial3.elements.len(), 6);
+    let dynamic: Vec<_> = tutorial3.links.iter().filter(|link| link.dynamic).collect();
+    assert_eq!(dynamic.len(), 2);
+    assert_eq!(dynamic[0].caps.as_deref(), Some("audio/x-raw"));
+    assert_eq!(tutorial3.bus.on_eos, EosPolicy::Stop);
+}
+
+#[test]
+fn inconsistent_descriptions_are_rejected() {
+    common::init();
+    let undeclared = PipelineConfig::from_toml(
+        r#"
+        [[elements]]
+        name = "source"
+        factory = "videotestsrc"
+        [[links]]
+        src = "source"
+        sink = "sink"
+        "#,
+    )
+    .unwrap();
+    assert!(matches!(undeclared.validate(), Err(Error::Config(_))));
+
+    let missing =
+        PipelineConfig::from_yaml("elements:\n  - name: source\n    factory: notaplugin\n")
+            .unwrap();
+    assert!(matches!(missing.validate(), Err(Error::MissingPlugins(_))));
+
+    let unknown_property = PipelineConfig::from_toml(
+        r#"
+        [[elements]]
+        name = "source"
+        factory = "videotestsrc"
+        properties = { colour = "red" }
+        "#,
+    )
+    .unwrap();
+    assert!(matches!(
+        unknown_property.build(),
+        Err(Error::Property { .. })
+    ));
+
+    assert!(PipelineConfig::from_toml("elements = 3").is_err());
+}
+
+fn source_with(properties: &str) -> PipelineConfig {
+    PipelineConfig::from_toml(&format!(
+        "[[elements]]\nname = \"source\"\nfactory = \"videotestsrc\"\nproperties = {{ {} }}\n",
+        properties
+    ))
+    .unwrap()
+}
+
+#[test]
+fn property_values_are_validated() {
+    common::init();
+    for invalid in &[
+        "pattern = \"nonsense\"",
+        "num-buffers = \"abc\"",
+        "num-buffers = -5",
+        "is-live = 2",
+    ] {
+        assert!(
+            matches!(source_with(invalid).validate(), Err(Error::Config(_))),
+            "{} was accepted",
+            invalid
+        );
+    }
+
+    // Quoted numbers, enums by nick or by number
+    let pipeline = source_with("pattern = \"ball\", num-buffers = \"10\"")
+        .build()
+        .unwrap();
+    let source = pipeline.get_by_name("source").unwrap();
+    assert_eq!(
+        source
+            .get_property("num-buffers")
+            .unwrap()
+            .get_some::<i32>()
+            .unwrap(),
+        10
+    );
+    let pattern = streams::value_to_string(&source.get_property("pattern").unwrap());
+    let by_number = config::property_value(&source, "pattern", &PropertyValue::Int(18)).unwrap();
+    assert_eq!(streams::value_to_string(&by_number), pattern);
+}
+
+#[test]
+fn static_pipeline_is_built_and_played() {
+    common::init();
+    let description = PipelineConfig::from_toml(
+        r#"
+        [[elements]]
+        name = "source"
+        factory = "videotestsrc"
+        properties = { pattern = "snow", num-buffers = 20, is-live = false }
+        [[elements]]
+        name = "sink"
+        factory = "fakesink"
+        [[links]]
+        src = "source"
+        sink = "sink"
+        caps = "video/x-raw,width=160,height=120"
+        [bus]
+        timeout_seconds = 20
+        "#,
+    )
+    .unwrap();
+    let pipeline = description.build().unwrap();
+    let source = pipeline.get_by_name("source").unwrap();
+    assert_eq!(
+        source
+            .get_property("num-buffers")
+            .unwrap()
+            .get_some::<i32>()
+            .unwrap(),
+        20
+    );
+    config::run(&pipeline, &description.bus).unwrap();
+}
+
+#[test]
+fn dynamic_pads_are_linked_by_caps() {
+    common::init();
+    let description = PipelineConfig::from_yaml(&format!(
+        "elements:
+  - name: source
+    factory: uridecodebin
+    properties:
+      uri: {uri}
+  - name: audio-convert
+    factory: audioconvert
+  - name: audio-sink
+    factory: fakesink
+  - name: video-sink
+    factory: fakesink
+links:
+  - src: source
+    sink: audio-convert
+    dynamic: true
+    caps: audio/x-raw
+  - src: source
+    sink: video-sink
+    dynamic: true
+    caps: video/x-raw
+  - src: audio-convert
+    sink: audio-sink
+bus:
+  timeout_seconds: 20
+",
+        uri = common::fixture_uri()
+    ))
+    .unwrap();
+    let pipeline = description.build().unwrap();
+    config::run(&pipeline, &description.bus).unwrap();
+
+    for sink in &["audio-convert", "video-sink"] {
+        let pad = pipeline
+            .get_by_name(sink)
+            .unwrap()
+            .get_static_pad("sink")
+            .unwrap();
+        assert!(pad.is_linked(), "{} was not linked", sink);
+    }
+}
@@ -0,0 +1,503 @@
+// Headless tests building each tutorial's pipeline with test sources and fake sinks
+extern crate gstreamer as gst;
+extern crate gstreamer_app as gst_app;
+
+mod common;
+
+use gst::prelude::*;
+use gstreamer_rust::audio;
+use gstreamer_rust::buffering::Buffering;
+use gstreamer_rust::error::{self, Error};
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::player::{Player, PlayerEvent};
+use gstreamer_rust::plugins::{self, MissingPlugin};
+use gstreamer_rust::seek::{SeekMode, Seeker};
+use gstreamer_rust::streams;
+use gstreamer_rust::subtitles;
+use gstreamer_rust::tutorial3::StreamKind;
+use gstreamer_rust::{tutorial1, tutorial2, tutorial3, tutorial4};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn tutorial1_playbin_plays_to_eos() {
+    common::init();
+    // A local path is converted into a file:// URI
+    let media = MediaSource::parse(common::fixture_path().to_str().unwrap());
+    assert!(media.uri().unwrap().starts_with("file://"));
+
+    let playbin = tutorial1::build_pipeline(&media).unwrap();
+    common::use_fake_sinks(&playbin, false);
+
+    let report = common::run_to_eos(&playbin, Duration::from_secs(30));
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(report.reached(gst::State::Playing));
+    assert!(report.eos);
+}
+
+#[test]
+fn tutorial2_videotestsrc_reaches_appsink() {
+    common::init();
+    let sink = gst::ElementFactory::make("appsink", Some("sink")).unwrap();
+    let appsink = sink.clone().dynamic_cast::<gst_app::AppSink>().unwrap();
+    let samples = Arc::new(AtomicUsize::new(0));
+    let counter = samples.clone();
+    appsink.set_callbacks(
+        gst_app::AppSinkCallbacks::new()
+            .new_sample(move |appsink| {
+                let _ = appsink.pull_sample();
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+
+    let (pipeline, source) = tutorial2::build_pipeline_with_sink(sink).unwrap();
+    source.set_property_from_str("pattern", "snow");
+    source.set_property("num-buffers", &10i32).unwrap();
+
+    let report = common::run_to_eos(pipeline.upcast_ref(), Duration::from_secs(10));
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(report.eos);
+    assert_eq!(samples.load(Ordering::SeqCst), 10);
+}
+
+// Count the sinks of the branches tutorial3 added to the pipeline
+fn count_sinks(pipeline: &gst::Pipeline) -> usize {
+    pipeline
+        .iterate_sinks()
+        .into_iter()
+        .filter_map(Result::ok)
+        .count()
+}
+
+#[test]
+fn tutorial3_links_audio_and_video_pads_of_uridecodebin() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let pipeline = tutorial3::build_pipeline_with_sinks(&media, |_| {
+        Ok(common::fake_sink_with_name(None, false))
+    })
+    .unwrap();
+
+    let report = common::run_to_eos(pipeline.upcast_ref(), Duration::from_secs(30));
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(report.eos);
+
+    // Both the audio and the video pad added by uridecodebin must have got their own branch
+    assert_eq!(count_sinks(&pipeline), 2);
+}
+
+#[test]
+fn tutorial3_links_static_pad_of_audiotestsrc() {
+    common::init();
+    let pipeline = tutorial3::build_pipeline_with_sinks(&MediaSource::AudioTest, |kind| {
+        assert_eq!(kind, tutorial3::StreamKind::Audio);
+        Ok(common::fake_sink_with_name(None, false))
+    })
+    .unwrap();
+    common::limit_buffers(&pipeline, "source", 20);
+
+    // The static pad is linked while building the pipeline
+    assert_eq!(count_sinks(&pipeline), 1);
+
+    let report = common::run_to_eos(pipeline.upcast_ref(), Duration::from_secs(10));
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(report.eos);
+}
+
+#[test]
+fn tutorial3_stream_kind_from_caps() {
+    use tutorial3::StreamKind;
+    assert_eq!(
+        StreamKind::from_caps_name("audio/x-raw"),
+        Some(StreamKind::Audio)
+    );
+    assert_eq!(
+        StreamKind::from_caps_name("video/x-raw"),
+        Some(StreamKind::Video)
+    );
+    assert_eq!(
+        StreamKind::from_caps_name("text/x-raw"),
+        Some(StreamKind::Text)
+    );
+    assert_eq!(StreamKind::from_caps_name("video/x-h264"), None);
+}
+
+// Play tutorial4 on its main loop until `done` returns true, it terminated or 20s passed
+fn drive_tutorial4<F>(player_state: tutorial4::PlayerState, done: F) -> tutorial4::PlayerState
+where
+    F: Fn(&tutorial4::PlayerState) -> bool + 'static,
+{
+    let deadline = Instant::now() + Duration::from_secs(20);
+    // Position events come every 100ms, so the deadline is checked often enough
+    tutorial4::play_until(player_state, move |player_state| {
+        done(player_state) || Instant::now() > deadline
+    })
+    .unwrap()
+}
+
+#[test]
+fn tutorial4_loops_between_30s_and_35s() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    // Synchronized sinks, so that the loop is played in real time
+    common::use_fake_sinks(&playbin, true);
+
+    let player_state = tutorial4::PlayerState::new(playbin);
+    let player_state = drive_tutorial4(player_state, |player_state| player_state.loops_done > 0);
+
+    assert!(player_state.error.is_none(), "{:?}", player_state.error);
+    assert!(!player_state.terminate, "Player terminated before looping");
+    assert!(player_state.seek_enabled);
+    assert!(player_state.first_seek_done);
+    assert_eq!(player_state.loops_done, 1);
+}
+
+#[test]
+fn tutorial4_repeats_loop_then_plays_to_eos() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, false);
+
+    let ab_loop = tutorial4::AbLoop {
+        start: gst::SECOND,
+        end: 2 * gst::SECOND,
+        repeat: Some(3),
+    };
+    let player_state = tutorial4::PlayerState::with_loop(playbin, Some(ab_loop));
+    let player_state = drive_tutorial4(player_state, |_| false);
+
+    assert!(player_state.error.is_none(), "{:?}", player_state.error);
+    assert_eq!(player_state.loops_done, 3);
+    // Terminated by EOS after leaving the loop
+    assert!(player_state.terminate);
+}
+
+#[test]
+fn missing_element_is_reported() {
+    common::init();
+    match error::make_element("nonexistentelement", None) {
+        Err(Error::MissingElement(factory)) => assert_eq!(factory, "nonexistentelement"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn tutorial3_returns_bus_error_for_missing_file() {
+    common::init();
+    let media = MediaSource::Uri("file:///nonexistent/gstreamer-rust.ogg".to_string());
+    match tutorial3::run(&media) {
+        Err(Error::Bus { .. }) => (),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn preflight_reports_missing_plugins() {
+    common::init();
+    assert!(plugins::check(&tutorial3::required_elements(&MediaSource::AudioTest)).is_ok());
+
+    match plugins::check(&["fakesink", "nonexistentelement", "x264enc"]) {
+        Err(Error::MissingPlugins(missing)) => {
+            assert!(missing.iter().any(|m| m.name == "nonexistentelement"));
+            assert!(missing.iter().all(|m| m.name != "fakesink"));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let x264 = MissingPlugin::for_element("x264enc");
+    assert_eq!(x264.package, Some("gst-plugins-ugly"));
+}
+
+// Wait for a message of the given type, panicking after 10s
+fn wait_for(bus: &gst::Bus, message_type: gst::MessageType) {
+    let msg = bus.timed_pop_filtered(10 * gst::SECOND, &[message_type, gst::MessageType::Error]);
+    match msg.as_ref().map(|msg| msg.view()) {
+        Some(gst::MessageView::Error(err)) => panic!("{}", Error::from_message(err)),
+        Some(_) => (),
+        None => panic!("Timeout waiting for {:?}", message_type),
+    }
+}
+
+#[test]
+fn seeker_steps_frames_forward_and_backward() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, true);
+    let bus = playbin.get_bus().unwrap();
+
+    error::set_state(&playbin, gst::State::Paused).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    let mut seeker = Seeker::new(SeekMode::Accurate);
+    seeker.seek(&playbin, 10 * gst::SECOND).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    seeker.step(&playbin, 5).unwrap();
+    wait_for(&bus, gst::MessageType::StepDone);
+    let position = playbin.query_position::<gst::ClockTime>().unwrap();
+    assert!(position > 10 * gst::SECOND, "Position {}", position);
+
+    // Stepping backward reverses the playback direction
+    seeker.step(&playbin, -5).unwrap();
+    wait_for(&bus, gst::MessageType::StepDone);
+    assert!(seeker.rate < 0.0);
+
+    error::set_state(&playbin, gst::State::Null).unwrap();
+}
+
+#[test]
+fn seeker_lands_in_the_selected_mode() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, true);
+    let bus = playbin.get_bus().unwrap();
+
+    error::set_state(&playbin, gst::State::Paused).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    // The fixture has a keyframe every second
+    let target = 10_500 * gst::MSECOND;
+    let tolerance = 150 * gst::MSECOND;
+    for &mode in SeekMode::ALL.iter() {
+        Seeker::new(mode).seek(&playbin, target).unwrap();
+        wait_for(&bus, gst::MessageType::AsyncDone);
+        let position = playbin.query_position::<gst::ClockTime>().unwrap();
+        let landed = match mode {
+            SeekMode::Accurate => position + tolerance >= target && position <= target + tolerance,
+            SeekMode::SnapBefore => position <= target + tolerance,
+            SeekMode::SnapAfter => position + tolerance >= target,
+            SeekMode::KeyUnit => {
+                position + gst::SECOND >= target && position <= target + gst::SECOND
+            }
+        };
+        assert!(landed, "{} seek landed at {}", mode.name(), position);
+    }
+
+    error::set_state(&playbin, gst::State::Null).unwrap();
+}
+
+#[test]
+fn seeker_changes_rate_and_direction() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, true);
+    let bus = playbin.get_bus().unwrap();
+
+    error::set_state(&playbin, gst::State::Paused).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    let mut seeker = Seeker::default();
+    seeker.faster(&playbin).unwrap();
+    assert_eq!(seeker.rate, 1.25);
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    seeker.reverse(&playbin).unwrap();
+    assert_eq!(seeker.rate, -1.25);
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    seeker.slower(&playbin).unwrap();
+    assert_eq!(seeker.rate, -1.0);
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    // Out of range rates are refused and the current rate is kept
+    assert!(seeker.set_rate(&playbin, 8.0).is_err());
+    assert_eq!(seeker.rate, -1.0);
+
+    error::set_state(&playbin, gst::State::Null).unwrap();
+}
+
+#[test]
+fn player_reports_events_on_main_loop() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, false);
+
+    let context = glib::MainContext::new();
+    context.push_thread_default();
+    let main_loop = glib::MainLoop::new(Some(&context), false);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let handler_events = events.clone();
+    let handler_loop = main_loop.clone();
+    let player = Player::new(playbin, move |event| {
+        let terminated = match event {
+            PlayerEvent::Eos | PlayerEvent::Error(_) => true,
+            _ => false,
+        };
+        handler_events.borrow_mut().push(event);
+        if terminated {
+            handler_loop.quit();
+        }
+    })
+    .unwrap();
+    player.play().unwrap();
+    main_loop.run();
+    player.stop().unwrap();
+    drop(player);
+    context.pop_thread_default();
+
+    let events = events.borrow();
+    assert!(events.iter().any(|event| match event {
+        PlayerEvent::StateChanged { current, .. } => *current == gst::State::Playing,
+        _ => false,
+    }));
+    assert!(events.iter().any(|event| match event {
+        PlayerEvent::Position { duration, .. } => *duration > gst::SECOND,
+        _ => false,
+    }));
+    match events.last() {
+        Some(PlayerEvent::Eos) => (),
+        event => panic!("Expected EOS, got {:?}", event),
+    }
+}
+
+#[test]
+fn streams_are_described_and_selectable() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, false);
+    let bus = playbin.get_bus().unwrap();
+
+    error::set_state(&playbin, gst::State::Paused).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+
+    let video = streams::collect(&playbin, StreamKind::Video);
+    assert_eq!(video.len(), 1);
+    let caps = &video[0].caps;
+    assert!(caps.iter().any(|(field, _)| field == "width"), "{:?}", caps);
+    assert!(
+        caps.iter().any(|(field, _)| field == "framerate"),
+        "{:?}",
+        caps
+    );
+
+    let audio = streams::collect(&playbin, StreamKind::Audio);
+    assert_eq!(audio.len(), 1);
+    let caps = &audio[0].caps;
+    assert!(caps.iter().any(|(field, _)| field == "rate"), "{:?}", caps);
+    assert!(
+        caps.iter().any(|(field, _)| field == "channels"),
+        "{:?}",
+        caps
+    );
+
+    assert!(streams::collect(&playbin, StreamKind::Text).is_empty());
+    assert_eq!(streams::current(&playbin, StreamKind::Audio), Some(0));
+    streams::select(&playbin, StreamKind::Audio, 0).unwrap();
+
+    error::set_state(&playbin, gst::State::Null).unwrap();
+}
+
+#[test]
+fn external_subtitles_add_a_text_stream() {
+    common::init();
+    let srt = std::env::temp_dir().join(format!(
+        "gstreamer-rust-subtitles-{}.srt",
+        std::process::id()
+    ));
+    std::fs::write(
+        &srt,
+        "1\n00:00:01,000 --> 00:00:04,000\nHello\n\n2\n00:00:05,000 --> 00:00:08,000\nWorld\n",
+    )
+    .unwrap();
+
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, false);
+    subtitles::set_external(&playbin, srt.to_str().unwrap()).unwrap();
+    subtitles::set_font(&playbin, "Sans Bold 24").unwrap();
+    let bus = playbin.get_bus().unwrap();
+
+    error::set_state(&playbin, gst::State::Paused).unwrap();
+    wait_for(&bus, gst::MessageType::AsyncDone);
+    assert_eq!(streams::collect(&playbin, StreamKind::Text).len(), 1);
+
+    subtitles::select_track(&playbin, None).unwrap();
+    assert!(!subtitles::is_enabled(&playbin));
+    subtitles::select_track(&playbin, Some(0)).unwrap();
+    assert!(subtitles::is_enabled(&playbin));
+    assert_eq!(streams::current(&playbin, StreamKind::Text), Some(0));
+
+    error::set_state(&playbin, gst::State::Null).unwrap();
+    let _ = std::fs::remove_file(srt);
+}
+
+#[test]
+fn playbin_volume_mute_and_audio_sink() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let playbin = media.make_player(Some("playbin")).unwrap();
+
+    audio::set_volume(&playbin, 0.25).unwrap();
+    assert!((audio::volume(&playbin) - 0.25).abs() < 1e-6);
+    assert!(audio::set_volume(&playbin, -1.0).is_err());
+    // Above the volume slider, amplified by playbin
+    audio::set_volume(&playbin, 2.5).unwrap();
+    assert!((audio::volume(&playbin) - 2.5).abs() < 1e-6);
+    assert!(audio::set_volume(&playbin, 11.0).is_err());
+
+    audio::set_muted(&playbin, true).unwrap();
+    assert!(audio::is_muted(&playbin));
+
+    assert!(audio::available_sinks().contains(&"fakesink"));
+    audio::set_sink(&playbin, "fakesink").unwrap();
+    assert!(audio::set_sink(&playbin, "nonexistentsink").is_err());
+}
+
+#[test]
+fn buffering_holds_playback_until_the_buffer_is_full() {
+    let mut buffering = Buffering::default();
+    assert_eq!(buffering.request(gst::State::Playing), gst::State::Playing);
+    assert_eq!(buffering.update(20), Some(gst::State::Paused));
+    assert_eq!(buffering.update(60), None);
+    // Playing again waits for the buffer
+    assert_eq!(buffering.request(gst::State::Playing), gst::State::Paused);
+    assert!(!buffering.clock_lost());
+    assert_eq!(buffering.update(100), Some(gst::State::Playing));
+    assert!(buffering.clock_lost());
+
+    // Paused by the user, a full buffer doesn't resume the playback
+    buffering.request(gst::State::Paused);
+    assert_eq!(buffering.update(10), None);
+    assert_eq!(buffering.update(100), None);
+
+    // Live sources are never held
+    let mut live = Buffering::default();
+    live.request(gst::State::Playing);
+    live.state_changed(gst::StateChangeSuccess::NoPreroll);
+    assert!(live.is_live());
+    assert_eq!(live.update(10), None);
+    assert_eq!(live.request(gst::State::Playing), gst::State::Playing);
+}
+
+#[test]
+fn network_stream_is_buffered_until_eos() {
+    common::init();
+    if !plugins::find_missing(&["souphttpsrc"]).is_empty() {
+        println!("souphttpsrc is not installed, skipping");
+        return;
+    }
+    let url = common::serve_file(&common::fixture_path());
+    let playbin = MediaSource::Uri(url).make_player(Some("playbin")).unwrap();
+    common::use_fake_sinks(&playbin, false);
+
+    let player_state = tutorial4::PlayerState::with_loop(playbin, None);
+    let player_state = drive_tutorial4(player_state, |_| false);
+
+    assert!(player_state.error.is_none(), "{:?}", player_state.error);
+    assert!(player_state.terminate, "Playback didn't reach EOS");
+    assert!(!player_state.live);
+    assert_eq!(player_state.buffering, Some(100));
+}
@@ -0,0 +1,82 @@
+// Playlist files, navigation, shuffle and repeat modes
+use std::path::Path;
+
+use gstreamer_rust::playlist::{self, Playlist, RepeatMode};
+
+fn uris(names: &[&str]) -> Vec<String> {
+    names
+        .iter()
+        .map(|name| format!("file:///media/{}", name))
+        .collect()
+}
+
+#[test]
+fn playlist_files_are_parsed() {
+    let base = Path::new("/media");
+    let m3u = "#EXTM3U\n#EXTINF:123,Sintel\nsintel.webm\n\nhttps://example.com/b.ogg\n";
+    assert_eq!(
+        playlist::parse_m3u(m3u, base),
+        vec![
+            "file:///media/sintel.webm".to_string(),
+            "https://example.com/b.ogg".to_string()
+        ]
+    );
+
+    let pls = "[playlist]\nFile2=https://example.com/b.ogg\nTitle1=Sintel\nFile1=sintel.webm\n\
+               NumberOfEntries=2\nVersion=2\n日本=x\nFile=x\n";
+    assert_eq!(
+        playlist::parse_pls(pls, base),
+        vec![
+            "file:///media/sintel.webm".to_string(),
+            "https://example.com/b.ogg".to_string()
+        ]
+    );
+
+    assert!(playlist::is_playlist_file("list.M3U8"));
+    assert!(playlist::is_playlist_file("radio.pls"));
+    assert!(!playlist::is_playlist_file("sintel.webm"));
+}
+
+#[test]
+fn playlist_is_navigated_with_repeat_modes() {
+    assert!(Playlist::new(Vec::new()).is_err());
+
+    let mut playlist = Playlist::new(uris(&["a", "b"])).unwrap();
+    assert_eq!(playlist.current(), "file:///media/a");
+    assert_eq!(playlist.previous(), None);
+    assert_eq!(playlist.advance(), Some("file:///media/b"));
+    assert_eq!(playlist.advance(), None);
+
+    playlist.set_repeat_mode(RepeatMode::One);
+    assert_eq!(playlist.advance(), Some("file:///media/b"));
+    // Skipping still moves, and wraps around
+    assert_eq!(playlist.next(), Some("file:///media/a"));
+
+    playlist.set_repeat_mode(RepeatMode::All);
+    assert_eq!(playlist.previous(), Some("file:///media/b"));
+    assert_eq!(playlist.advance(), Some("file:///media/a"));
+    assert_eq!("all".parse::<RepeatMode>().unwrap(), RepeatMode::All);
+    assert!("sometimes".parse::<RepeatMode>().is_err());
+}
+
+#[test]
+fn shuffle_plays_every_entry_once() {
+    let mut playlist = Playlist::new(uris(&["a", "b", "c", "d", "e"])).unwrap();
+    playlist.next();
+    playlist.set_shuffle(true);
+    // The current entry keeps playing
+    assert_eq!(playlist.current(), "file:///media/b");
+
+    let mut played = vec![playlist.current().to_string()];
+    while let Some(uri) = playlist.advance() {
+        played.push(uri.to_string());
+    }
+    played.sort();
+    assert_eq!(played, uris(&["a", "b", "c", "d", "e"]));
+
+    // Unshuffling keeps the current entry too
+    let current = playlist.current().to_string();
+    playlist.set_shuffle(false);
+    assert!(!playlist.is_shuffled());
+    assert_eq!(playlist.current(), current);
+}
@@ -0,0 +1,114 @@
+// Recording what playbin plays through the tees in front of its sinks
+extern crate gstreamer as gst;
+extern crate gstreamer_pbutils as gst_pbutils;
+
+mod common;
+
+use gst::prelude::*;
+use gstreamer_rust::error::Error;
+use gstreamer_rust::record::{self, Recording};
+use gstreamer_rust::transcode::Profile;
+use std::thread;
+use std::time::Duration;
+
+fn make_playbin() -> gst::Element {
+    let playbin = gst::ElementFactory::make("playbin", None).unwrap();
+    playbin.set_property("uri", &common::fixture_uri()).unwrap();
+    common::use_fake_sinks(&playbin, true);
+    playbin
+}
+
+fn sink(playbin: &gst::Element, property: &str) -> gst::Element {
+    playbin
+        .get_property(property)
+        .unwrap()
+        .get::<gst::Element>()
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn tees_are_put_in_front_of_the_sinks_once() {
+    common::init();
+    let playbin = make_playbin();
+    assert!(record::use_tee_sinks(&playbin).unwrap());
+    let video_sink = sink(&playbin, "video-sink");
+    assert!(record::is_tee_sink(&video_sink));
+    // The fakesink set before is kept
+    let bin = video_sink.downcast_ref::<gst::Bin>().unwrap();
+    assert!(bin.get_by_name("videosink").is_some());
+
+    assert!(record::use_tee_sinks(&playbin).unwrap());
+    assert_eq!(sink(&playbin, "video-sink"), video_sink);
+    assert!(record::is_tee_sink(&sink(&playbin, "audio-sink")));
+
+    // Nothing is played yet
+    let location = std::env::temp_dir().join("gstreamer-rust-not-recorded.webm");
+    assert!(matches!(
+        Recording::start(&playbin, &location, Profile::WebM),
+        Err(Error::Record(_)) | Err(Error::MissingPlugins(_))
+    ));
+}
+
+#[test]
+fn recording_is_finalized_while_playback_goes_on() {
+    common::init();
+    let profile = match Profile::available().first() {
+        Some(profile) => *profile,
+        None => {
+            eprintln!("No encoding profile available, skipping");
+            return;
+        }
+    };
+    let playbin = make_playbin();
+    record::use_tee_sinks(&playbin).unwrap();
+    playbin.set_state(gst::State::Playing).unwrap();
+    let (res, _, _) = playbin.get_state(10 * gst::SECOND);
+    res.unwrap();
+    thread::sleep(Duration::from_secs(2));
+
+    let location = std::env::temp_dir().join(format!(
+        "gstreamer-rust-record-{}.{}",
+        std::process::id(),
+        profile.extension()
+    ));
+    let recording = Recording::start(&playbin, &location, profile).unwrap();
+    thread::sleep(Duration::from_secs(2));
+    let position = playbin.query_position::<gst::ClockTime>().unwrap();
+    // Stopping returns right away, the application is told once the file is finalized
+    let stopping = recording.stop();
+    let bus = playbin.get_bus().unwrap();
+    let msg = bus
+        .timed_pop_filtered(
+            record::STOP_TIMEOUT.as_secs() * gst::SECOND,
+            &[gst::MessageType::Application],
+        )
+        .unwrap();
+    assert_eq!(
+        msg.get_structure().unwrap().get_name(),
+        record::FINALIZED_MESSAGE
+    );
+    assert!(stopping.is_done());
+    assert_eq!(stopping.finish().unwrap(), location);
+
+    // The playback wasn't interrupted
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(playbin.get_current_state(), gst::State::Playing);
+    assert!(playbin.query_position::<gst::ClockTime>().unwrap() > position);
+    assert!(bus.pop_filtered(&[gst::MessageType::Error]).is_none());
+    playbin.set_state(gst::State::Null).unwrap();
+
+    // The recorded streams start at 0 and last about as long as the recording
+    let discoverer = gst_pbutils::Discoverer::new(10 * gst::SECOND).unwrap();
+    let uri = glib::filename_to_uri(&location, None).unwrap();
+    let info = discoverer.discover_uri(&uri).unwrap();
+    assert_eq!(info.get_video_streams().len(), 1);
+    assert_eq!(info.get_audio_streams().len(), 1);
+    let duration = info.get_duration().mseconds().unwrap();
+    assert!(
+        duration >= 1000 && duration < 5000,
+        "Recorded {}ms",
+        duration
+    );
+    std::fs::remove_file(&location).unwrap();
+}
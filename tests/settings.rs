@@ -0,0 +1,78 @@
+// Settings and playback states persisted between runs of the players
+extern crate gstreamer as gst;
+
+use gstreamer_rust::resume::{self, ResumeDb, ResumeState};
+use gstreamer_rust::settings::Settings;
+
+#[test]
+fn settings_are_saved_and_loaded() {
+    let path = std::env::temp_dir()
+        .join(format!("gstreamer-rust-settings-{}", std::process::id()))
+        .join("settings");
+
+    // Nothing saved yet
+    assert_eq!(Settings::load_from(&path), Settings::default());
+
+    let mut settings = Settings::default();
+    settings.set("volume", 0.5);
+    settings.set("mute", true);
+    settings.set("audio-sink", "pulsesink");
+    settings.save_to(&path).unwrap();
+
+    let loaded = Settings::load_from(&path);
+    assert_eq!(loaded, settings);
+    assert_eq!(loaded.parse::<f64>("volume"), Some(0.5));
+    assert_eq!(loaded.parse::<bool>("mute"), Some(true));
+    assert_eq!(loaded.get("audio-sink"), Some("pulsesink"));
+    assert_eq!(loaded.parse::<f64>("audio-sink"), None);
+
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+fn resume_state(position_seconds: u64, updated: u64) -> ResumeState {
+    ResumeState {
+        position: position_seconds * 1_000_000_000,
+        duration: Some(600 * 1_000_000_000),
+        video_track: Some(0),
+        audio_track: Some(1),
+        text_track: None,
+        volume: Some(0.5),
+        rate: 1.5,
+        updated,
+    }
+}
+
+#[test]
+fn resume_states_are_saved_per_uri() {
+    let path = std::env::temp_dir()
+        .join(format!("gstreamer-rust-resume-{}", std::process::id()))
+        .join("resume.json");
+    assert!(ResumeDb::load_from(&path).is_empty());
+
+    let mut db = ResumeDb::default();
+    db.record("file:///movie.webm", resume_state(95, 1));
+    db.record("file:///clip.webm", resume_state(2, 1));
+    // Too close to the start, it starts over next time
+    assert_eq!(db.len(), 1);
+    db.save_to(&path).unwrap();
+
+    let mut loaded = ResumeDb::load_from(&path);
+    assert_eq!(loaded, db);
+    let state = loaded.resumable("file:///movie.webm").unwrap();
+    assert_eq!(state.position(), 95 * gst::SECOND);
+    assert_eq!(state.audio_track, Some(1));
+    assert_eq!(state.rate, 1.5);
+
+    // Played to the end, forgotten
+    loaded.record("file:///movie.webm", resume_state(598, 2));
+    assert!(loaded.get("file:///movie.webm").is_none());
+
+    // Only the most recent entries are kept
+    for i in 0..resume::MAX_ENTRIES as u64 + 1 {
+        loaded.record(&format!("file:///{}.webm", i), resume_state(60, 10 + i));
+    }
+    assert_eq!(loaded.len(), resume::MAX_ENTRIES);
+    assert!(loaded.get("file:///0.webm").is_none());
+
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
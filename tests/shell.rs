@@ -0,0 +1,115 @@
+// Commands, pipeline control and tab completion of the interactive shell
+extern crate gstreamer as gst;
+
+mod common;
+
+use gst::prelude::*;
+use gstreamer_rust::error::Error;
+use gstreamer_rust::shell::{Command, Completion, Shell};
+
+#[test]
+fn commands_are_parsed() {
+    assert_eq!(
+        "set source pattern snow".parse::<Command>().unwrap(),
+        Command::Set {
+            element: "source".to_string(),
+            property: "pattern".to_string(),
+            value: "snow".to_string()
+        }
+    );
+    assert_eq!(
+        "  pause ".parse::<Command>().unwrap(),
+        Command::State(gst::State::Paused)
+    );
+    // gst-launch style lines don't need the launch command
+    assert_eq!(
+        "videotestsrc ! fakesink".parse::<Command>().unwrap(),
+        Command::Launch("videotestsrc ! fakesink".to_string())
+    );
+    assert!(matches!(
+        "get source".parse::<Command>(),
+        Err(Error::Usage(_))
+    ));
+    assert!(matches!("dance".parse::<Command>(), Err(Error::Usage(_))));
+}
+
+#[test]
+fn launched_pipeline_is_controlled() {
+    common::init();
+    let mut shell = Shell::new();
+    assert!(matches!(
+        shell.execute(&Command::State(gst::State::Playing)),
+        Err(Error::Usage(_))
+    ));
+    assert!(matches!(
+        shell.launch("videotestsrc ! nosuchelement"),
+        Err(Error::Launch(_))
+    ));
+
+    shell
+        .execute(
+            &"launch videotestsrc name=source is-live=true ! fakesink name=sink"
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        shell
+            .execute(&"set sink sync false".parse().unwrap())
+            .unwrap(),
+        "sync = false"
+    );
+    assert_eq!(
+        shell.execute(&"get sink sync".parse().unwrap()).unwrap(),
+        "false"
+    );
+    assert!(matches!(
+        shell.execute(&"set source colour red".parse().unwrap()),
+        Err(Error::Property { .. })
+    ));
+    assert!(matches!(
+        shell.execute(&"set source pattern bogus".parse().unwrap()),
+        Err(Error::Config(_))
+    ));
+
+    let elements = shell.execute(&Command::Elements).unwrap();
+    assert!(elements.contains("source (videotestsrc)"), "{}", elements);
+    let pads = shell.execute(&Command::Pads("source".to_string())).unwrap();
+    assert!(pads.starts_with("src (Src) linked to"), "{}", pads);
+
+    shell.execute(&Command::State(gst::State::Paused)).unwrap();
+    shell.execute(&Command::Quit).unwrap();
+    assert!(shell.pipeline().is_none());
+}
+
+#[test]
+fn commands_elements_and_properties_are_completed() {
+    common::init();
+    let mut shell = Shell::new();
+    shell
+        .launch("videotestsrc name=source is-live=true ! fakesink name=sink")
+        .unwrap();
+    let mut completion = Completion::new();
+    completion.update(&shell);
+
+    assert_eq!(
+        completion.candidates("la", 2),
+        (0, vec!["launch".to_string()])
+    );
+    assert_eq!(
+        completion.candidates("set so", 6),
+        (4, vec!["source".to_string()])
+    );
+    assert_eq!(
+        completion.candidates("get source patt", 15),
+        (11, vec!["pattern".to_string()])
+    );
+
+    let (start, factories) = completion.candidates("launch videotes", 15);
+    assert_eq!(start, 7);
+    assert!(factories.contains(&"videotestsrc".to_string()));
+    let (_, properties) = completion.candidates("launch videotestsrc num-", 24);
+    assert_eq!(properties, vec!["num-buffers".to_string()]);
+    let (_, factories) = completion.candidates("videotestsrc ! fakes", 20);
+    assert_eq!(factories, vec!["fakesink".to_string()]);
+}
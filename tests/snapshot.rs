@@ -0,0 +1,112 @@
+// Frames of playbin saved as PNG or JPEG images
+extern crate gstreamer as gst;
+
+mod common;
+
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::snapshot::{self, ImageFormat, Options, Size};
+use std::path::Path;
+
+fn args(args: &[&str]) -> Args {
+    Args::parse(args.iter().map(|arg| arg.to_string()), &[])
+}
+
+#[test]
+fn snapshot_options_are_parsed() {
+    assert_eq!(Options::from_args(&args(&["movie.webm"])).unwrap(), None);
+
+    let options = Options::from_args(&args(&[
+        "--snapshot-at",
+        "1:05",
+        "--snapshot-output",
+        "frame.JPG",
+        "--snapshot-size",
+        "320x",
+    ]))
+    .unwrap()
+    .unwrap();
+    assert_eq!(options.position, 65 * gst::SECOND);
+    assert_eq!(
+        ImageFormat::from_path(&options.output),
+        Some(ImageFormat::Jpeg)
+    );
+    assert_eq!(
+        options.size,
+        Size {
+            width: Some(320),
+            height: None
+        }
+    );
+
+    assert!(
+        Options::from_args(&args(&["--snapshot-at", "5", "--snapshot-output", "a.gif"])).is_err()
+    );
+    assert!(Options::from_args(&args(&["--snapshot-at", "5", "--snapshot-size", "0x10"])).is_err());
+    assert!(Options::from_args(&args(&["--snapshot-at", "soon"])).is_err());
+}
+
+#[test]
+fn sizes_keep_the_aspect_ratio() {
+    assert_eq!(Size::default().scale(640, 360), (640, 360));
+    assert_eq!(Size::parse("320x").unwrap().scale(640, 360), (320, 180));
+    assert_eq!(Size::parse("x90").unwrap().scale(640, 360), (160, 90));
+    assert_eq!(Size::parse("100x100").unwrap().scale(640, 360), (100, 100));
+    assert_eq!(Size::parse("100"), None);
+}
+
+// Width and height from the IHDR chunk of a PNG file
+fn png_size(data: &[u8]) -> (u32, u32) {
+    assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+    let read = |offset: usize| {
+        u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    (read(16), read(20))
+}
+
+#[test]
+fn frame_is_saved_headlessly_at_a_position() {
+    common::init();
+    let media = MediaSource::Uri(common::fixture_uri());
+    let dir = std::env::temp_dir();
+
+    let png = dir.join(format!(
+        "gstreamer-rust-snapshot-{}.png",
+        std::process::id()
+    ));
+    let options = Options {
+        position: 5 * gst::SECOND,
+        output: png.clone(),
+        size: Size::parse("80x").unwrap(),
+    };
+    snapshot::snapshot_at(&media, &options).unwrap();
+    // The fixture is 160x120
+    assert_eq!(png_size(&std::fs::read(&png).unwrap()), (80, 60));
+    std::fs::remove_file(&png).unwrap();
+
+    let jpeg = dir.join(format!(
+        "gstreamer-rust-snapshot-{}.jpg",
+        std::process::id()
+    ));
+    let options = Options {
+        position: 10 * gst::SECOND,
+        output: jpeg.clone(),
+        size: Size::default(),
+    };
+    snapshot::snapshot_at(&media, &options).unwrap();
+    assert_eq!(&std::fs::read(&jpeg).unwrap()[..2], b"\xff\xd8");
+    std::fs::remove_file(&jpeg).unwrap();
+
+    // Synthetic sources have no playbin to take the frame from
+    let options = Options {
+        position: gst::SECOND,
+        output: Path::new("never.png").to_path_buf(),
+        size: Size::default(),
+    };
+    assert!(snapshot::snapshot_at(&MediaSource::VideoTest, &options).is_err());
+}
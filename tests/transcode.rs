@@ -0,0 +1,87 @@
+// Transcoding with encodebin and the encoding profiles
+extern crate gstreamer as gst;
+extern crate gstreamer_pbutils as gst_pbutils;
+
+mod common;
+
+use gst::prelude::*;
+use gst_pbutils::prelude::*;
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::media::MediaSource;
+use gstreamer_rust::transcode::{self, Options, Profile};
+use std::path::Path;
+
+fn args(args: &[&str]) -> Args {
+    Args::parse(args.iter().map(|arg| arg.to_string()), &[])
+}
+
+#[test]
+fn profile_is_picked_from_options_or_extension() {
+    let options = Options::from_args(&args(&["videotestsrc", "out.MKV"])).unwrap();
+    assert_eq!(options.media, MediaSource::VideoTest);
+    assert_eq!(options.profile, Profile::Matroska);
+
+    let options =
+        Options::from_args(&args(&["videotestsrc", "out.bin", "--profile", "ogg"])).unwrap();
+    assert_eq!(options.profile, Profile::Ogg);
+    assert_eq!(
+        Options::from_args(&args(&["videotestsrc", "out.bin"]))
+            .unwrap()
+            .profile,
+        Profile::WebM
+    );
+    assert!(Options::from_args(&args(&["videotestsrc"])).is_err());
+    assert!(Options::from_args(&args(&["a", "b", "--profile", "avi"])).is_err());
+
+    assert_eq!(Profile::from_path(Path::new("a.mp4")), Some(Profile::Mp4));
+    assert_eq!(
+        transcode::progress(5 * gst::SECOND, 20 * gst::SECOND),
+        Some(25.0)
+    );
+    assert_eq!(
+        transcode::progress(5 * gst::SECOND, gst::CLOCK_TIME_NONE),
+        None
+    );
+}
+
+#[test]
+fn encoding_profiles_are_built() {
+    common::init();
+    for profile in &Profile::ALL {
+        let encoding_profile = profile.encoding_profile().unwrap();
+        assert_eq!(encoding_profile.get_name().as_deref(), Some(profile.name()));
+    }
+}
+
+#[test]
+fn fixture_is_transcoded_with_audio_and_video() {
+    common::init();
+    let profile = match Profile::available().first() {
+        Some(profile) => *profile,
+        None => {
+            eprintln!("No encoding profile available, skipping");
+            return;
+        }
+    };
+    let output = std::env::temp_dir().join(format!(
+        "gstreamer-rust-transcode-{}.{}",
+        std::process::id(),
+        profile.extension()
+    ));
+    let media = MediaSource::Uri(common::fixture_uri());
+    let pipeline = transcode::build_pipeline(&media, &output, profile).unwrap();
+    transcode::transcode(&pipeline).unwrap();
+
+    let discoverer = gst_pbutils::Discoverer::new(10 * gst::SECOND).unwrap();
+    let uri = glib::filename_to_uri(&output, None).unwrap();
+    let info = discoverer.discover_uri(&uri).unwrap();
+    assert_eq!(info.get_video_streams().len(), 1);
+    assert_eq!(info.get_audio_streams().len(), 1);
+    let duration = info.get_duration().seconds().unwrap();
+    assert!(
+        duration + 1 >= common::FIXTURE_SECONDS,
+        "Transcoded only {}s",
+        duration
+    );
+    std::fs::remove_file(&output).unwrap();
+}
@@ -0,0 +1,106 @@
+// Parts of the GTK player which don't need a display, only built with --features tutorial5
+#![cfg(feature = "tutorial5")]
+
+use gdk::enums::key;
+use gstreamer_rust::cli::Args;
+use gstreamer_rust::seek::SeekMode;
+use gstreamer_rust::tutorial5::{Letterbox, Options, Renderer, Shortcut};
+
+#[test]
+fn renderer_is_selectable() {
+    let args = Args::parse(vec!["--renderer".to_string(), "appsink".to_string()], &[]);
+    assert_eq!(
+        Options::from_args(&args).unwrap().renderer,
+        Renderer::AppSink
+    );
+    assert_eq!(
+        Options::from_args(&Args::default()).unwrap().renderer,
+        Renderer::Auto
+    );
+    let args = Args::parse(vec!["--renderer=xv".to_string()], &[]);
+    assert!(Options::from_args(&args).is_err());
+}
+
+#[test]
+fn seek_mode_is_selectable() {
+    let args = Args::parse(vec!["--seek-mode=accurate".to_string()], &[]);
+    assert_eq!(
+        Options::from_args(&args).unwrap().seek_mode,
+        SeekMode::Accurate
+    );
+    assert_eq!(
+        Options::from_args(&Args::default()).unwrap().seek_mode,
+        SeekMode::KeyUnit
+    );
+}
+
+#[test]
+fn frames_are_letterboxed() {
+    // Wider than the area, black bars above and below
+    let letterbox = Letterbox::fit(1920.0, 1080.0, 800.0, 600.0);
+    assert_eq!(
+        letterbox,
+        Letterbox {
+            x: 0.0,
+            y: 75.0,
+            width: 800.0,
+            height: 450.0
+        }
+    );
+
+    // Taller than the area, black bars on the sides
+    let letterbox = Letterbox::fit(480.0, 640.0, 800.0, 400.0);
+    assert_eq!(letterbox.height, 400.0);
+    assert_eq!(letterbox.width, 300.0);
+    assert_eq!(letterbox.x, 250.0);
+    assert_eq!(letterbox.y, 0.0);
+}
+
+#[test]
+fn subtitle_options_are_read() {
+    let args = Args::parse(
+        vec![
+            "--subtitles".to_string(),
+            "movie.srt".to_string(),
+            "--subtitle-font=Sans 20".to_string(),
+            "--no-subtitles".to_string(),
+        ],
+        Options::SWITCHES,
+    );
+    let options = Options::from_args(&args).unwrap();
+    assert_eq!(options.subtitles.as_deref(), Some("movie.srt"));
+    assert_eq!(options.subtitle_font.as_deref(), Some("Sans 20"));
+    assert!(options.no_subtitles);
+
+    let args = Args::parse(
+        vec!["--subtitles".to_string(), "movie.webm".to_string()],
+        Options::SWITCHES,
+    );
+    assert!(Options::from_args(&args).is_err());
+}
+
+#[test]
+fn keys_are_mapped_to_shortcuts() {
+    let none = gdk::ModifierType::empty();
+    assert_eq!(
+        Shortcut::from_key(key::space, none),
+        Some(Shortcut::PlayPause)
+    );
+    assert_eq!(
+        Shortcut::from_key(key::Left, none),
+        Some(Shortcut::Seek(-5))
+    );
+    assert_eq!(
+        Shortcut::from_key(key::Right, gdk::ModifierType::SHIFT_MASK),
+        Some(Shortcut::Seek(60))
+    );
+    assert_eq!(
+        Shortcut::from_key(key::F, gdk::ModifierType::SHIFT_MASK),
+        Some(Shortcut::ToggleFullscreen)
+    );
+    assert_eq!(
+        Shortcut::from_key(key::period, none),
+        Some(Shortcut::Step(1))
+    );
+    assert_eq!(Shortcut::from_key(key::a, none), None);
+}